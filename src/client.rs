@@ -0,0 +1,173 @@
+// Client.rs - Holds the state StarWM tracks for each managed window
+use crate::geometry::Rect;
+use crate::stack::Layer;
+use std::time::{Duration, Instant};
+
+// WM_HINTS flag that marks a window as urgent
+pub const URGENCY_HINT: u32 = 1 << 8;
+// How often clients are pinged, and how long they have to answer
+pub const PING_INTERVAL: Duration = Duration::from_secs(5);
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+// WM_NORMAL_HINTS flags for the sizes a client has given
+const P_MIN_SIZE: u32 = 1 << 4;
+const P_MAX_SIZE: u32 = 1 << 5;
+const P_RESIZE_INC: u32 = 1 << 6;
+const P_BASE_SIZE: u32 = 1 << 8;
+// How long to wait for a client to redraw before resizing it again anyway
+pub const SYNC_TIMEOUT: Duration = Duration::from_millis(200);
+
+// Client struct that holds information about a specific window
+pub struct Client {
+    pub class: String,
+    pub protocols: Vec<u32>,
+    pub opacity: Option<f64>,
+    pub urgent: Option<Instant>,
+    pub ping: Option<Instant>,
+    pub pong: Instant,
+    pub hung: bool,
+    pub kill_offered: bool,
+    pub sync: Option<SyncCounter>,
+    pub fullscreen: Option<Rect>,
+    pub fullscreen_monitors: Option<[u32; 4]>,
+    pub floating: bool,
+    pub float_geometry: Option<Rect>,
+    pub kept: Option<Layer>,
+}
+
+impl Client {
+    pub fn new(class: String, protocols: Vec<u32>) -> Self {
+        // Create a new client, with its WM_CLASS and the WM_PROTOCOLS it supports
+        Self {
+            class,
+            protocols,
+            opacity: None,
+            urgent: None,
+            ping: None,
+            pong: Instant::now(),
+            hung: false,
+            kill_offered: false,
+            sync: None,
+            fullscreen: None,
+            fullscreen_monitors: None,
+            floating: false,
+            float_geometry: None,
+            kept: None,
+        }
+    }
+
+    pub fn supports(&self, protocol: u32) -> bool {
+        // Check if this client takes part in a protocol
+        self.protocols.contains(&protocol)
+    }
+
+    pub fn ping_due(&self) -> bool {
+        // Check if it's time to send this client another ping
+        self.ping.is_none() && self.pong.elapsed() >= PING_INTERVAL
+    }
+
+    pub fn ping_expired(&self) -> bool {
+        // Check if this client has taken too long to answer a ping
+        self.ping.is_some_and(|sent| sent.elapsed() >= PING_TIMEOUT)
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        // Check if this client is demanding attention
+        self.urgent.is_some()
+    }
+
+    pub fn set_urgent(&mut self, urgent: bool) -> bool {
+        // Update the urgency of this client, returns true if it changed
+        if urgent == self.is_urgent() {
+            return false;
+        }
+        self.urgent = if urgent { Some(Instant::now()) } else { None };
+        true
+    }
+}
+
+// State of the _NET_WM_SYNC_REQUEST counter for a client
+pub struct SyncCounter {
+    pub alarm: u32,
+    pub value: i64,
+    pub sent: Option<Instant>,
+    pub deferred: Option<Rect>,
+}
+
+impl SyncCounter {
+    pub fn new(alarm: u32, value: i64) -> Self {
+        // Create a new sync counter, starting from the value it holds right now, watched by an
+        // alarm that goes off when it moves on
+        Self {
+            alarm,
+            value,
+            sent: None,
+            deferred: None,
+        }
+    }
+
+    pub fn timed_out(&self) -> bool {
+        // Check if there is no request outstanding, or if the client took too long
+        self.sent.is_none_or(|sent| sent.elapsed() >= SYNC_TIMEOUT)
+    }
+}
+
+// Sizes a client is willing to be resized to, from its WM_NORMAL_HINTS
+#[derive(Clone, Copy, Default)]
+pub struct SizeHints {
+    min: (i64, i64),
+    max: Option<(i64, i64)>,
+    inc: (i64, i64),
+    base: (i64, i64),
+}
+
+impl SizeHints {
+    pub fn new(hints: &[u32]) -> Self {
+        // Read the sizes out of a WM_NORMAL_HINTS property, leaving out those not given
+        let flags = hints.first().copied().unwrap_or_default();
+        let pair = |flag: u32, idx: usize| {
+            if flags & flag == 0 {
+                return None;
+            }
+            Some((i64::from(*hints.get(idx)?), i64::from(*hints.get(idx + 1)?)))
+        };
+        // The minimum and base sizes each stand in for the other when only one is given
+        let min = pair(P_MIN_SIZE, 5);
+        let base = pair(P_BASE_SIZE, 15);
+        Self {
+            min: min.or(base).unwrap_or((1, 1)),
+            max: pair(P_MAX_SIZE, 7),
+            inc: pair(P_RESIZE_INC, 9).unwrap_or((1, 1)),
+            base: base.or(min).unwrap_or_default(),
+        }
+    }
+
+    pub fn constrain(&self, w: i64, h: i64) -> (i64, i64) {
+        // Fit a size to the steps a client resizes in, between its smallest and largest sizes
+        let fit = |size: i64, min: i64, max: Option<i64>, inc: i64, base: i64| {
+            let mut size = size.max(min);
+            if let Some(max) = max.filter(|&max| max > 0) {
+                size = size.min(max);
+            }
+            if inc > 1 && size > base {
+                size -= (size - base) % inc;
+            }
+            size.max(min).max(1)
+        };
+        (
+            fit(
+                w,
+                self.min.0,
+                self.max.map(|m| m.0),
+                self.inc.0,
+                self.base.0,
+            ),
+            fit(
+                h,
+                self.min.1,
+                self.max.map(|m| m.1),
+                self.inc.1,
+                self.base.1,
+            ),
+        )
+    }
+}
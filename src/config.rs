@@ -1,5 +1,6 @@
 // Config.rs - Handles configuration of the editor
 use crate::key::Key;
+use crate::layout::Masters;
 use crate::StarMan;
 use std::collections::HashMap;
 
@@ -9,8 +10,23 @@ pub type Handler = fn(&mut StarMan) -> ();
 // Configuration that holds the key bindings within the window manager
 pub struct Config {
     pub key_bindings: HashMap<Key, Handler>,
+    pub rules: HashMap<String, Rule>,
     pub unfocused_border: WindowBorder,
     pub focused_border: WindowBorder,
+    pub urgent_border: WindowBorder,
+    pub hung_border: WindowBorder,
+    pub workspace_switch: WorkspaceSwitch,
+    pub focus_model: FocusModel,
+    pub raise: Raise,
+    pub masters: Masters,
+    pub gaps: Gaps,
+    pub smart_gaps: bool,
+    pub smart_borders: bool,
+    pub layouts: Vec<&'static str>,
+    pub tab_font: &'static str,
+    pub tab_text: u32,
+    pub cycle_overlay: bool,
+    pub resize_button: u8,
 }
 
 impl Config {
@@ -18,14 +34,59 @@ impl Config {
         // Start a fresh configuration struct
         Self {
             key_bindings: HashMap::new(),
+            rules: HashMap::new(),
             unfocused_border: WindowBorder {
                 size: 2,
                 colour: 0x383838,
+                opacity: 1.0,
             },
             focused_border: WindowBorder {
                 size: 2,
                 colour: 0x006755,
+                opacity: 1.0,
             },
+            urgent_border: WindowBorder {
+                size: 2,
+                colour: 0xa83232,
+                opacity: 1.0,
+            },
+            hung_border: WindowBorder {
+                size: 2,
+                colour: 0x8c6b1f,
+                opacity: 1.0,
+            },
+            workspace_switch: WorkspaceSwitch::Focus,
+            focus_model: FocusModel::Sloppy,
+            raise: Raise::OnFocus,
+            masters: Masters {
+                count: 1,
+                ratio: 0.55,
+            },
+            gaps: Gaps {
+                inner: 8,
+                outer: 8,
+                enabled: true,
+            },
+            smart_gaps: true,
+            smart_borders: true,
+            layouts: vec![
+                "floating",
+                "tile",
+                "wide",
+                "centered",
+                "columns",
+                "grid",
+                "spiral",
+                "dwindle",
+                "tree",
+                "tabbed",
+                "stacked",
+                "scrolling",
+            ],
+            tab_font: "fixed",
+            tab_text: 0xffffff,
+            cycle_overlay: true,
+            resize_button: 3,
         }
     }
 
@@ -38,10 +99,68 @@ impl Config {
         // Get a handler function when a key binding occurs
         self.key_bindings.get(key)
     }
+
+    pub fn add_rule(&mut self, class: &str, rule: Rule) {
+        // Add a rule for windows with a specific WM_CLASS
+        self.rules.insert(class.to_string(), rule);
+    }
+
+    pub fn rule(&self, class: &str) -> Option<&Rule> {
+        // Get the rule for windows with a specific WM_CLASS
+        self.rules.get(class)
+    }
 }
 
-// Struct to hold window border information
+// Struct to hold window border information, and the opacity that goes with it
 pub struct WindowBorder {
     pub size: u32,
     pub colour: u32,
+    pub opacity: f64,
+}
+
+// Space left between tiled windows (inner), and between them and the screen edge (outer)
+#[derive(Clone, Copy)]
+pub struct Gaps {
+    pub inner: u32,
+    pub outer: u32,
+    pub enabled: bool,
+}
+
+// What happens when switching to a workspace that another monitor is showing
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceSwitch {
+    // Pull it onto the focused monitor, giving the other monitor our old workspace
+    Greedy,
+    // Focus the monitor that is already showing it
+    Focus,
+}
+
+// How the pointer moves focus between windows
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FocusModel {
+    // Focus the window under the pointer, and nothing once it moves onto the desktop
+    FollowsMouse,
+    // Focus the window under the pointer, keeping it focused over the desktop
+    Sloppy,
+    // Focus a window when it's clicked, passing the click on to it
+    Click,
+}
+
+// When a window focused with the pointer is brought to the top
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Raise {
+    // As soon as it's focused
+    OnFocus,
+    // Only once it's clicked
+    OnClick,
+    // Only when focused from the keyboard
+    #[allow(dead_code)]
+    Never,
+}
+
+// Struct to hold settings that apply to windows of a specific class
+#[derive(Default)]
+pub struct Rule {
+    pub opacity: Option<f64>,
+    pub floating: bool,
 }
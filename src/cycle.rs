@@ -0,0 +1,41 @@
+// Cycle.rs - Handles stepping through windows in the order they were last used
+use crate::tabs::Strip;
+
+// Cycle struct that holds the windows being stepped through, while the modifier is held
+pub struct Cycle {
+    pub windows: Vec<u32>,
+    pub idx: usize,
+    pub overlay: Option<Strip>,
+}
+
+impl Cycle {
+    pub fn new(windows: Vec<u32>, overlay: Option<Strip>) -> Self {
+        // Start a cycle on the most recently used window, which is the one focused
+        Self {
+            windows,
+            idx: 0,
+            overlay,
+        }
+    }
+
+    pub fn step(&mut self, forward: bool) -> u32 {
+        // Move on to the next (or previous) window, wrapping around
+        let count = self.windows.len();
+        self.idx = if forward {
+            (self.idx + 1) % count
+        } else {
+            (self.idx + count - 1) % count
+        };
+        self.current()
+    }
+
+    pub fn current(&self) -> u32 {
+        // Get the window the cycle has got to
+        self.windows[self.idx]
+    }
+
+    pub fn original(&self) -> u32 {
+        // Get the window that was focused before the cycle started
+        self.windows[0]
+    }
+}
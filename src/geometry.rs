@@ -0,0 +1,141 @@
+// Geometry.rs - Handles positions and sizes of things on the screen
+use std::convert::TryFrom;
+
+// Directions that things on the screen can be found or moved in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// Rectangle struct that holds a position and a size
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub x: i64,
+    pub y: i64,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Rect {
+    pub fn new(x: i64, y: i64, w: u32, h: u32) -> Self {
+        // Create a new rectangle
+        Self { x, y, w, h }
+    }
+
+    pub fn right(&self) -> i64 {
+        // Get the X coordinate just past the right edge
+        self.x + i64::from(self.w)
+    }
+
+    pub fn bottom(&self) -> i64 {
+        // Get the Y coordinate just past the bottom edge
+        self.y + i64::from(self.h)
+    }
+
+    pub fn centre(&self) -> (i64, i64) {
+        // Get the point in the middle of this rectangle
+        (
+            self.x + i64::from(self.w / 2),
+            self.y + i64::from(self.h / 2),
+        )
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        // Check if a point lies within this rectangle
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        // Check if any part of this rectangle lies within another
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    pub fn inset(&self, left: u32, top: u32, right: u32, bottom: u32) -> Rect {
+        // Shrink this rectangle by an amount on each side, without turning it inside out
+        let w = self.w.saturating_sub(left + right).max(1);
+        let h = self.h.saturating_sub(top + bottom).max(1);
+        Rect::new(self.x + i64::from(left), self.y + i64::from(top), w, h)
+    }
+
+    pub fn clamp_into(&self, area: &Rect) -> Rect {
+        // Move this rectangle so that as much of it as possible lies within an area
+        let x = self.x.min(area.right() - i64::from(self.w)).max(area.x);
+        let y = self.y.min(area.bottom() - i64::from(self.h)).max(area.y);
+        Rect::new(x, y, self.w, self.h)
+    }
+
+    pub fn split_x(&self, width: u32) -> (Rect, Rect) {
+        // Cut this rectangle into a left part of a given width, and whatever is left over
+        let width = width.min(self.w);
+        (
+            Rect::new(self.x, self.y, width, self.h),
+            Rect::new(self.x + i64::from(width), self.y, self.w - width, self.h),
+        )
+    }
+
+    pub fn split_y(&self, height: u32) -> (Rect, Rect) {
+        // Cut this rectangle into a top part of a given height, and whatever is left over
+        let height = height.min(self.h);
+        (
+            Rect::new(self.x, self.y, self.w, height),
+            Rect::new(self.x, self.y + i64::from(height), self.w, self.h - height),
+        )
+    }
+
+    pub fn rows(&self, count: usize) -> Vec<Rect> {
+        // Cut this rectangle into rows of (nearly) equal height
+        let mut rest = *self;
+        (0..count)
+            .map(|idx| {
+                let left = u32::try_from(count - idx).unwrap_or(u32::MAX);
+                let (row, remainder) = rest.split_y(rest.h / left);
+                rest = remainder;
+                row
+            })
+            .collect()
+    }
+
+    pub fn columns(&self, count: usize) -> Vec<Rect> {
+        // Cut this rectangle into columns of (nearly) equal width
+        let mut rest = *self;
+        (0..count)
+            .map(|idx| {
+                let left = u32::try_from(count - idx).unwrap_or(u32::MAX);
+                let (column, remainder) = rest.split_x(rest.w / left);
+                rest = remainder;
+                column
+            })
+            .collect()
+    }
+
+    pub fn rescale(&self, from: &Rect, to: &Rect) -> Rect {
+        // Map this rectangle from one area into another, keeping its relative position and size
+        let scale = |value: i64, old: u32, new: u32| value * i64::from(new) / i64::from(old.max(1));
+        Rect::new(
+            to.x + scale(self.x - from.x, from.w, to.w),
+            to.y + scale(self.y - from.y, from.h, to.h),
+            u32::try_from(scale(i64::from(self.w), from.w, to.w).max(1)).unwrap_or(u32::MAX),
+            u32::try_from(scale(i64::from(self.h), from.h, to.h).max(1)).unwrap_or(u32::MAX),
+        )
+    }
+
+    pub fn distance_towards(&self, other: &Rect, direction: Direction) -> Option<i64> {
+        // Measure how far away another rectangle is in a direction, if it lies that way at all
+        let (x, y) = self.centre();
+        let (ox, oy) = other.centre();
+        let (ahead, across) = match direction {
+            Direction::Left => (x - ox, oy - y),
+            Direction::Right => (ox - x, oy - y),
+            Direction::Up => (y - oy, ox - x),
+            Direction::Down => (oy - y, ox - x),
+        };
+        // Favour things lined up with us over things that are closer but off to the side
+        (ahead > 0).then(|| ahead + across.abs() * 2)
+    }
+}
@@ -12,14 +12,35 @@ pub type SymTable = HashMap<u8, Vec<String>>;
 
 // Common combinations
 pub const META_SHIFT: ModMask = META | SHIFT;
+pub const META_CONTROL: ModMask = META | CONTROL;
+pub const META_ALT: ModMask = META | ALT;
+pub const META_ALT_SHIFT: ModMask = META | ALT | SHIFT;
 /*
 pub const CONTROL_SHIFT: ModMask = CONTROL | SHIFT;
 pub const CONTROL_ALT_SHIFT: ModMask = CONTROL | ALT | SHIFT;
 pub const CONTROL_ALT: ModMask = CONTROL | ALT;
-pub const META_ALT: ModMask = META | ALT;
-pub const META_ALT_SHIFT: ModMask = META | ALT | SHIFT;
 */
 
+// Modifier keys that keep a window cycle going for as long as they're held
+const HOLD_KEYS: [&str; 8] = [
+    "Super_L",
+    "Super_R",
+    "Alt_L",
+    "Alt_R",
+    "Meta_L",
+    "Meta_R",
+    "Control_L",
+    "Control_R",
+];
+
+// Modifiers that those keys set, for checking if any are still held
+pub const HOLD_MASK: ModMask = META | ALT | CONTROL;
+
+pub fn is_hold_key(code: &str) -> bool {
+    // Check if letting go of a key should finish a window cycle
+    HOLD_KEYS.contains(&code)
+}
+
 // Representation of a key, with modifiers
 #[derive(PartialEq, Eq, Hash)]
 pub struct Key {
@@ -0,0 +1,379 @@
+// Layout.rs - Handles the automatic arrangement of windows within a workspace
+#![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+use crate::geometry::{Direction, Rect};
+use crate::scroll::Scrolling;
+use crate::tree::{Axis, Tree};
+use std::convert::TryFrom;
+
+// Height of each tab in the strip drawn by tabbed and stacked layouts
+pub const TAB_HEIGHT: u32 = 20;
+
+// Trait for anything that can arrange the windows of a workspace
+pub trait Layout {
+    // Get the name of this layout, as shown to the user
+    fn name(&self) -> &'static str;
+    // Work out where each window should go within an area, windows left out stay put
+    fn arrange(&mut self, windows: &[u32], area: Rect, masters: Masters) -> Vec<(u32, Rect)>;
+    // Check if this layout places windows itself, rather than leaving them where they're put
+    fn tiles(&self) -> bool {
+        true
+    }
+    // Take note of a new window, placed by the target window, for layouts where that matters
+    fn insert(&mut self, _window: u32, _target: Option<u32>) {}
+    // Carry out an action around the focused window, returns true if windows need arranging
+    fn handle(&mut self, _message: Message, _focus: u32) -> bool {
+        false
+    }
+    // Swap the places of two windows, for layouts that keep track of where windows are
+    fn swap(&mut self, _window: u32, _other: u32) {}
+    // Move a window to one side of another, for layouts that keep track of where windows are
+    fn place_beside(&mut self, _window: u32, _target: u32, _edge: Direction) {}
+    // Take note of the focused window, returns true if windows need arranging
+    fn focus(&mut self, _window: u32) -> bool {
+        false
+    }
+    // Find the window next to another in a direction, for layouts that keep some out of view
+    fn towards(&self, _window: u32, _direction: Direction) -> Option<u32> {
+        None
+    }
+    // Check if dragging an edge of a window moves a boundary, given if the window is a master
+    fn resizes(&self, _master: bool, _edge: Direction) -> bool {
+        false
+    }
+    // Work out where the tab for each window goes, for layouts that draw a tab strip
+    fn tabs(&self, _windows: &[u32], _area: Rect) -> Vec<(u32, Rect)> {
+        vec![]
+    }
+}
+
+// Actions that can be sent to layouts that are arranged by hand
+#[derive(Clone, Copy, Debug)]
+pub enum Message {
+    // Put the focused window in a new container, split along an axis
+    Split(Axis),
+    // Choose which side of the focused window the next window goes
+    Preselect(Direction),
+    // Swap the focused window with its neighbour in a direction
+    Move(Direction),
+    // Grow (or shrink) the focused window's share of its container
+    Resize(f64),
+    // Tidy away containers that aren't needed
+    Flatten,
+    // Turn the container holding the focused window a quarter turn
+    Rotate,
+    // Pull the next column's window into the focused window's column
+    Consume,
+    // Push the focused window out into a column of its own
+    Expel,
+    // Move the focused column on to the next preset width
+    CycleWidth,
+}
+
+// How many windows a layout gives pride of place to, and how much of the area they get
+#[derive(Clone, Copy)]
+pub struct Masters {
+    pub count: usize,
+    pub ratio: f64,
+}
+
+impl Masters {
+    fn split<'a>(&self, windows: &'a [u32]) -> (&'a [u32], &'a [u32]) {
+        // Split windows into the masters and the rest of the stack
+        windows.split_at(self.count.min(windows.len()))
+    }
+
+    fn share(&self, length: u32) -> u32 {
+        // Get the part of a length that the masters get
+        (f64::from(length) * self.ratio) as u32
+    }
+}
+
+pub fn from_name(name: &str) -> Option<Box<dyn Layout>> {
+    // Create one of the built-in layouts from its name
+    let layout: Box<dyn Layout> = match name {
+        "floating" => Box::new(Floating),
+        "tile" => Box::new(Tile),
+        "wide" => Box::new(Wide),
+        "centered" => Box::new(CenteredMaster),
+        "columns" => Box::new(Columns),
+        "grid" => Box::new(Grid),
+        "spiral" => Box::new(Spiral),
+        "dwindle" => Box::new(Dwindle),
+        "tree" => Box::new(Tree::new()),
+        "tabbed" => Box::new(Tabbed),
+        "stacked" => Box::new(Stacked),
+        "scrolling" => Box::new(Scrolling::new()),
+        _ => return None,
+    };
+    Some(layout)
+}
+
+fn place(windows: &[u32], areas: Vec<Rect>) -> Vec<(u32, Rect)> {
+    // Pair windows up with the areas they've been given
+    windows.iter().copied().zip(areas).collect()
+}
+
+// Floating layout, where windows stay wherever they are put
+pub struct Floating;
+
+impl Layout for Floating {
+    fn name(&self) -> &'static str {
+        "floating"
+    }
+
+    fn arrange(&mut self, _windows: &[u32], _area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        // Nothing to arrange, windows are moved around by hand
+        vec![]
+    }
+
+    fn tiles(&self) -> bool {
+        false
+    }
+}
+
+// Master-stack layout, with the master windows on the left and the rest stacked on the right
+pub struct Tile;
+
+impl Layout for Tile {
+    fn name(&self) -> &'static str {
+        "tile"
+    }
+
+    fn resizes(&self, master: bool, edge: Direction) -> bool {
+        // Only the edge between the masters and the stack moves
+        matches!(
+            (master, edge),
+            (true, Direction::Right) | (false, Direction::Left)
+        )
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, masters: Masters) -> Vec<(u32, Rect)> {
+        // Split the area between masters and stack, unless one side would be empty
+        let (master, stack) = masters.split(windows);
+        let (left, right) = if master.is_empty() {
+            (Rect::default(), area)
+        } else if stack.is_empty() {
+            (area, Rect::default())
+        } else {
+            area.split_x(masters.share(area.w))
+        };
+        let mut arranged = place(master, left.rows(master.len()));
+        arranged.extend(place(stack, right.rows(stack.len())));
+        arranged
+    }
+}
+
+// Wide layout, with the master windows along the top and the rest side by side underneath
+pub struct Wide;
+
+impl Layout for Wide {
+    fn name(&self) -> &'static str {
+        "wide"
+    }
+
+    fn resizes(&self, master: bool, edge: Direction) -> bool {
+        // Only the edge between the masters and the stack moves
+        matches!(
+            (master, edge),
+            (true, Direction::Down) | (false, Direction::Up)
+        )
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, masters: Masters) -> Vec<(u32, Rect)> {
+        // Split the area between masters and stack, unless one side would be empty
+        let (master, stack) = masters.split(windows);
+        let (top, bottom) = if master.is_empty() {
+            (Rect::default(), area)
+        } else if stack.is_empty() {
+            (area, Rect::default())
+        } else {
+            area.split_y(masters.share(area.h))
+        };
+        let mut arranged = place(master, top.columns(master.len()));
+        arranged.extend(place(stack, bottom.columns(stack.len())));
+        arranged
+    }
+}
+
+// Centered master layout, with the masters in the middle and the stack either side of them
+pub struct CenteredMaster;
+
+impl Layout for CenteredMaster {
+    fn name(&self) -> &'static str {
+        "centered"
+    }
+
+    fn resizes(&self, _master: bool, edge: Direction) -> bool {
+        // The masters' column is resized from either side
+        matches!(edge, Direction::Left | Direction::Right)
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, masters: Masters) -> Vec<(u32, Rect)> {
+        let (master, stack) = masters.split(windows);
+        if master.is_empty() || stack.is_empty() {
+            return place(windows, area.columns(windows.len()));
+        }
+        // With just one other window, there's only need for one side
+        if stack.len() == 1 {
+            let (middle, right) = area.split_x(masters.share(area.w));
+            let mut arranged = place(master, middle.rows(master.len()));
+            arranged.extend(place(stack, vec![right]));
+            return arranged;
+        }
+        // Otherwise take a column from the middle, and share the stack between both sides
+        let width = masters.share(area.w);
+        let (left, rest) = area.split_x((area.w - width) / 2);
+        let (middle, right) = rest.split_x(width);
+        let (mut left_stack, mut right_stack) = (vec![], vec![]);
+        for (idx, &window) in stack.iter().enumerate() {
+            if idx % 2 == 0 {
+                right_stack.push(window);
+            } else {
+                left_stack.push(window);
+            }
+        }
+        let mut arranged = place(master, middle.rows(master.len()));
+        arranged.extend(place(&left_stack, left.rows(left_stack.len())));
+        arranged.extend(place(&right_stack, right.rows(right_stack.len())));
+        arranged
+    }
+}
+
+// Columns layout, with every window side by side at the same width
+pub struct Columns;
+
+impl Layout for Columns {
+    fn name(&self) -> &'static str {
+        "columns"
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        place(windows, area.columns(windows.len()))
+    }
+}
+
+// Grid layout, with windows in (nearly) as many rows as columns
+pub struct Grid;
+
+impl Layout for Grid {
+    fn name(&self) -> &'static str {
+        "grid"
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        // Find the smallest square that fits every window, then drop any unused rows
+        let mut columns = 0;
+        while columns * columns < windows.len() {
+            columns += 1;
+        }
+        if columns == 0 {
+            return vec![];
+        }
+        let rows = windows.len().div_ceil(columns);
+        // Any gaps are left in the last row, so its windows get wider
+        area.rows(rows)
+            .into_iter()
+            .zip(windows.chunks(columns))
+            .flat_map(|(row, chunk)| place(chunk, row.columns(chunk.len())))
+            .collect()
+    }
+}
+
+// Spiral layout, where each window takes half of what's left, turning clockwise inwards
+pub struct Spiral;
+
+impl Layout for Spiral {
+    fn name(&self) -> &'static str {
+        "spiral"
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        fibonacci(windows, area, true)
+    }
+}
+
+// Dwindle layout, where each window takes half of what's left, heading to the bottom right
+pub struct Dwindle;
+
+impl Layout for Dwindle {
+    fn name(&self) -> &'static str {
+        "dwindle"
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        fibonacci(windows, area, false)
+    }
+}
+
+fn fibonacci(windows: &[u32], area: Rect, spiral: bool) -> Vec<(u32, Rect)> {
+    // Give each window half of the remaining area, alternating between splitting across and
+    // down, a spiral also works its way back round to the left and top
+    let mut rest = area;
+    let mut arranged = vec![];
+    for (idx, &window) in windows.iter().enumerate() {
+        if idx == windows.len() - 1 {
+            arranged.push((window, rest));
+            break;
+        }
+        let turn = if spiral { idx % 4 } else { idx % 2 };
+        let (taken, remainder) = match turn {
+            0 => rest.split_x(rest.w / 2),
+            1 => rest.split_y(rest.h / 2),
+            2 => {
+                let (remainder, taken) = rest.split_x(rest.w - rest.w / 2);
+                (taken, remainder)
+            }
+            _ => {
+                let (remainder, taken) = rest.split_y(rest.h - rest.h / 2);
+                (taken, remainder)
+            }
+        };
+        arranged.push((window, taken));
+        rest = remainder;
+    }
+    arranged
+}
+
+// Tabbed layout, where every window shares the area below a row of tabs
+pub struct Tabbed;
+
+impl Layout for Tabbed {
+    fn name(&self) -> &'static str {
+        "tabbed"
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        let (_, rest) = area.split_y(TAB_HEIGHT);
+        windows.iter().map(|&window| (window, rest)).collect()
+    }
+
+    fn tabs(&self, windows: &[u32], area: Rect) -> Vec<(u32, Rect)> {
+        let (strip, _) = area.split_y(TAB_HEIGHT);
+        place(windows, strip.columns(windows.len()))
+    }
+}
+
+// Stacked layout, where every window shares the area below a list of tabs, one per row
+pub struct Stacked;
+
+impl Layout for Stacked {
+    fn name(&self) -> &'static str {
+        "stacked"
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        let (_, rest) = area.split_y(stacked_height(windows, area));
+        windows.iter().map(|&window| (window, rest)).collect()
+    }
+
+    fn tabs(&self, windows: &[u32], area: Rect) -> Vec<(u32, Rect)> {
+        let (strip, _) = area.split_y(stacked_height(windows, area));
+        place(windows, strip.rows(windows.len()))
+    }
+}
+
+fn stacked_height(windows: &[u32], area: Rect) -> u32 {
+    // Get the height of a list of tabs, leaving at least half of the area for the windows
+    let count = u32::try_from(windows.len()).unwrap_or(u32::MAX);
+    TAB_HEIGHT.saturating_mul(count).min(area.h / 2)
+}
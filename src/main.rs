@@ -9,15 +9,30 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::unreadable_literal)]
 
+mod client;
 mod config;
+mod cycle;
+mod geometry;
 #[macro_use]
 mod utils;
 mod key;
+mod layout;
+mod monitor;
 mod mouse;
+mod scroll;
+mod stack;
+mod startup;
+mod tabs;
+mod tree;
 mod window;
 mod wm;
 
-use key::{META, META_SHIFT, NONE};
+use config::{FocusModel, Gaps, Raise, Rule, WorkspaceSwitch};
+use geometry::Direction;
+use key::{META, META_ALT, META_ALT_SHIFT, META_CONTROL, META_SHIFT, NONE};
+use layout::{Floating, Masters, Message, Stacked, Tabbed, Tile};
+use scroll::Scrolling;
+use tree::Axis;
 use wm::StarMan;
 
 // List of commands to run within the WM
@@ -25,6 +40,7 @@ const ROFI: &str = "rofi -show run";
 const ALACRITTY: &str = "alacritty";
 const MAIM: &str = "maim -suB --delay=0.1 | xclip -selection clipboard -t image/png";
 
+#[allow(clippy::too_many_lines)]
 fn main() {
     // Initialise and run StarWM
     let mut starman = StarMan::new();
@@ -44,6 +60,133 @@ fn main() {
     starman.bind((META_SHIFT, "8"), |s| s.move_window_to_workspace(7));
     starman.bind((META_SHIFT, "9"), |s| s.move_window_to_workspace(8));
     starman.bind((META_SHIFT, "0"), |s| s.move_window_to_workspace(9));
+    // Focus the next or previous monitor on [Meta] + [Period] or [Comma]
+    starman.bind((META, "period"), StarMan::focus_next_monitor);
+    starman.bind((META, "comma"), StarMan::focus_prev_monitor);
+    // Focus the monitor in a direction on [Meta] + [Control] + [Arrow]
+    starman.bind((META_CONTROL, "Left"), |s| {
+        s.focus_monitor_towards(Direction::Left);
+    });
+    starman.bind((META_CONTROL, "Right"), |s| {
+        s.focus_monitor_towards(Direction::Right);
+    });
+    starman.bind((META_CONTROL, "Up"), |s| {
+        s.focus_monitor_towards(Direction::Up);
+    });
+    starman.bind((META_CONTROL, "Down"), |s| {
+        s.focus_monitor_towards(Direction::Down);
+    });
+    // Take window to the next or previous monitor on [Meta] + [Shift] + [Period] or [Comma]
+    starman.bind((META_SHIFT, "period"), |s| {
+        s.follow_window_to_monitor(s.next_monitor());
+    });
+    starman.bind((META_SHIFT, "comma"), |s| {
+        s.follow_window_to_monitor(s.prev_monitor());
+    });
+    // Swap workspaces with the next monitor on [Meta] + [Control] + [Period]
+    starman.bind((META_CONTROL, "period"), |s| {
+        s.swap_workspaces_with_monitor(s.next_monitor());
+    });
+    // Cycle through recently used windows on [Meta] + [Tab], or on every workspace with [Alt]
+    // held too, going backwards with [Shift]
+    starman.bind((META, "Tab"), StarMan::cycle_windows);
+    starman.bind((META_SHIFT, "Tab"), StarMan::cycle_windows_back);
+    starman.bind((META_ALT, "Tab"), StarMan::cycle_all_windows);
+    starman.bind((META_ALT_SHIFT, "Tab"), StarMan::cycle_all_windows_back);
+    // Jump to the most recent urgent window on [Meta] + [U]
+    starman.bind((META, "u"), StarMan::focus_urgent);
+    // Change opacity of the focused window on [Meta] + [Brackets], reset on [Meta] + [Backslash]
+    starman.bind((META, "bracketright"), StarMan::opacity_increase);
+    starman.bind((META, "bracketleft"), StarMan::opacity_decrease);
+    starman.bind((META, "backslash"), StarMan::opacity_reset);
+    // Grow or shrink the gaps on [Meta] + [Equals] and [Minus], toggle them on [Meta] + [G]
+    starman.bind((META, "equal"), StarMan::grow_gaps);
+    starman.bind((META, "minus"), StarMan::shrink_gaps);
+    starman.bind((META, "g"), StarMan::toggle_gaps);
+    // Tile windows on [Meta] + [T], let them float on [Meta] + [Shift] + [T]
+    starman.bind((META, "t"), |s| s.set_layout(Tile));
+    starman.bind((META_SHIFT, "t"), |s| s.set_layout(Floating));
+    // Show windows as tabs on [Meta] + [W], or as a stack of tabs on [Meta] + [E]
+    starman.bind((META, "w"), |s| s.set_layout(Tabbed));
+    starman.bind((META, "e"), |s| s.set_layout(Stacked));
+    // Scroll through columns of windows on [Meta] + [Shift] + [S]
+    starman.bind((META_SHIFT, "s"), |s| s.set_layout(Scrolling::new()));
+    // Pull the next window into the focused column on [Meta] + [C], push it out on [Meta] + [X]
+    starman.bind((META, "c"), |s| s.send_layout(Message::Consume));
+    starman.bind((META, "x"), |s| s.send_layout(Message::Expel));
+    // Cycle the width of the focused column on [Meta] + [Z]
+    starman.bind((META, "z"), |s| s.send_layout(Message::CycleWidth));
+    // Switch to the next or previous tab on [Meta] + [J] and [K]
+    starman.bind((META, "j"), StarMan::next_tab);
+    starman.bind((META, "k"), StarMan::prev_tab);
+    // Cycle through the other layouts on [Meta] + [N]
+    starman.bind((META, "n"), StarMan::cycle_layout);
+    // Split the focused window side by side on [Meta] + [B], or one above the other on [Meta] + [V]
+    starman.bind((META, "b"), |s| {
+        s.send_layout(Message::Split(Axis::Horizontal));
+    });
+    starman.bind((META, "v"), |s| {
+        s.send_layout(Message::Split(Axis::Vertical));
+    });
+    // Choose where the next window goes on [Meta] + [Alt] + [Arrow]
+    starman.bind((META_ALT, "Left"), |s| {
+        s.send_layout(Message::Preselect(Direction::Left));
+    });
+    starman.bind((META_ALT, "Right"), |s| {
+        s.send_layout(Message::Preselect(Direction::Right));
+    });
+    starman.bind((META_ALT, "Up"), |s| {
+        s.send_layout(Message::Preselect(Direction::Up));
+    });
+    starman.bind((META_ALT, "Down"), |s| {
+        s.send_layout(Message::Preselect(Direction::Down));
+    });
+    // Focus the nearest window in a direction on [Meta] + [Arrow]
+    starman.bind((META, "Left"), |s| s.focus_towards(Direction::Left));
+    starman.bind((META, "Right"), |s| s.focus_towards(Direction::Right));
+    starman.bind((META, "Up"), |s| s.focus_towards(Direction::Up));
+    starman.bind((META, "Down"), |s| s.focus_towards(Direction::Down));
+    // Move the focused window in a direction on [Meta] + [Shift] + [Arrow]
+    starman.bind((META_SHIFT, "Left"), |s| s.move_towards(Direction::Left));
+    starman.bind((META_SHIFT, "Right"), |s| s.move_towards(Direction::Right));
+    starman.bind((META_SHIFT, "Up"), |s| s.move_towards(Direction::Up));
+    starman.bind((META_SHIFT, "Down"), |s| s.move_towards(Direction::Down));
+    // Swap the focused window with its neighbour on [Meta] + [Alt] + [Shift] + [Arrow]
+    starman.bind((META_ALT_SHIFT, "Left"), |s| {
+        s.swap_towards(Direction::Left);
+    });
+    starman.bind((META_ALT_SHIFT, "Right"), |s| {
+        s.swap_towards(Direction::Right);
+    });
+    starman.bind((META_ALT_SHIFT, "Up"), |s| s.swap_towards(Direction::Up));
+    starman.bind((META_ALT_SHIFT, "Down"), |s| {
+        s.swap_towards(Direction::Down);
+    });
+    // Grow or shrink the focused window on [Meta] + [Alt] + [Equals] and [Minus]
+    starman.bind((META_ALT, "equal"), |s| {
+        s.send_layout(Message::Resize(0.05));
+    });
+    starman.bind((META_ALT, "minus"), |s| {
+        s.send_layout(Message::Resize(-0.05));
+    });
+    // Tidy up the tree on [Meta] + [Alt] + [F], and turn it on [Meta] + [Alt] + [R]
+    starman.bind((META_ALT, "f"), |s| s.send_layout(Message::Flatten));
+    starman.bind((META_ALT, "r"), |s| s.send_layout(Message::Rotate));
+    // Resize the master area on [Meta] + [H] and [L]
+    starman.bind((META, "h"), StarMan::shrink_master);
+    starman.bind((META, "l"), StarMan::grow_master);
+    // Change the number of master windows on [Meta] + [I] and [D]
+    starman.bind((META, "i"), StarMan::add_master);
+    starman.bind((META, "d"), StarMan::remove_master);
+    // Promote the focused window to master on [Meta] + [Shift] + [Return]
+    starman.bind((META_SHIFT, "Return"), StarMan::promote_master);
+    // Rotate the windows on [Meta] + [R], backwards with [Meta] + [Shift] + [R]
+    starman.bind((META, "r"), StarMan::rotate_stack);
+    starman.bind((META_SHIFT, "r"), StarMan::rotate_stack_back);
+    // Toggle floating on the focused window on [Meta] + [Shift] + [Space]
+    starman.bind((META_SHIFT, "space"), StarMan::toggle_floating);
+    // Toggle fullscreen on [Meta] + [F]
+    starman.bind((META, "f"), StarMan::toggle_fullscreen);
     // Toggle monocle mode on [Meta] + [M]
     starman.bind((META, "m"), |s| {
         if s.workspace().get_monocle().is_none() {
@@ -56,12 +199,64 @@ fn main() {
     // Start application launcher on [Meta] + [Space]
     starman.bind((META, "space"), |_| cmd!(ROFI));
     // Start terminal on [Meta] + [Return]
-    starman.bind((META, "Return"), |_| cmd!(ALACRITTY));
+    starman.bind((META, "Return"), |s| s.spawn(ALACRITTY));
     // Screenshot on [Meta] + [S]
     starman.bind((META, "s"), |_| cmd!(MAIM));
     // Open rofi on search key
     starman.bind((NONE, "XF86Search"), |_| cmd!(ROFI));
 
+    // Make terminals slightly see-through
+    starman.rule(
+        "Alacritty",
+        Rule {
+            opacity: Some(0.95),
+            ..Rule::default()
+        },
+    );
+    // Let calculators float
+    starman.rule(
+        "Galculator",
+        Rule {
+            floating: true,
+            ..Rule::default()
+        },
+    );
+
+    // Give one master window 55% of the workspace
+    starman.masters(Masters {
+        count: 1,
+        ratio: 0.55,
+    });
+
+    // Leave gaps around windows, apart from on workspace 0, where they sit edge to edge
+    starman.gaps(Gaps {
+        inner: 10,
+        outer: 10,
+        enabled: true,
+    });
+    starman.workspace_gaps(
+        9,
+        Gaps {
+            inner: 0,
+            outer: 0,
+            enabled: false,
+        },
+    );
+    // Drop gaps and borders when a workspace has only one window to show
+    starman.smart_gaps(true);
+    starman.smart_borders(true);
+
+    // Pull workspaces onto the focused monitor, rather than jumping to them
+    starman.workspace_switch(WorkspaceSwitch::Greedy);
+
+    // Resize windows from the nearest edge or corner with [Meta] + middle-drag, rather than
+    // the default right-drag
+    starman.resize_button(2);
+
+    // Focus the window under the pointer, but only bring it to the top when it's clicked
+    starman.focus_model(FocusModel::Sloppy);
+    starman.raise(Raise::OnClick);
+
     // Run the window manager
     starman.run();
 }
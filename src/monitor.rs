@@ -0,0 +1,68 @@
+// Monitor.rs - Handles the physical outputs that make up the screen
+use crate::geometry::Rect;
+use xcb::randr;
+
+// Monitor struct that holds information about a specific output
+pub struct Monitor {
+    pub root: u32,
+    pub name: String,
+    pub geometry: Rect,
+    pub primary: bool,
+    pub workspace: usize,
+}
+
+impl Monitor {
+    pub fn new(root: u32, name: &str, geometry: Rect, primary: bool) -> Self {
+        // Create a new monitor, on the screen with this root window
+        Self {
+            root,
+            name: name.to_string(),
+            geometry,
+            primary,
+            workspace: 0,
+        }
+    }
+}
+
+pub fn get_monitors(conn: &xcb::Connection, root: u32, fallback: Rect) -> Vec<Monitor> {
+    // Ask RandR for each active output, falling back to the whole root window
+    let mut monitors: Vec<Monitor> = vec![];
+    let primary = randr::get_output_primary(conn, root)
+        .get_reply()
+        .map_or(xcb::NONE, |reply| reply.output());
+    if let Ok(resources) = randr::get_screen_resources_current(conn, root).get_reply() {
+        let timestamp = resources.config_timestamp();
+        for &output in resources.outputs() {
+            // Skip outputs that are disconnected or switched off
+            let Ok(info) = randr::get_output_info(conn, output, timestamp).get_reply() else {
+                continue;
+            };
+            if info.crtc() == xcb::NONE
+                || u32::from(info.connection()) != randr::CONNECTION_CONNECTED
+            {
+                continue;
+            }
+            let Ok(crtc) = randr::get_crtc_info(conn, info.crtc(), timestamp).get_reply() else {
+                continue;
+            };
+            let geometry = Rect::new(
+                i64::from(crtc.x()),
+                i64::from(crtc.y()),
+                u32::from(crtc.width()),
+                u32::from(crtc.height()),
+            );
+            // Mirrored outputs share the same area, so only keep one of them
+            if monitors.iter().any(|m| m.geometry == geometry) {
+                continue;
+            }
+            let name = String::from_utf8_lossy(info.name());
+            monitors.push(Monitor::new(root, &name, geometry, output == primary));
+        }
+    }
+    if monitors.is_empty() {
+        monitors.push(Monitor::new(root, "default", fallback, true));
+    }
+    // Put the primary output first, the way Xinerama numbers them, keeping the rest in order
+    monitors.sort_by_key(|m| !m.primary);
+    monitors
+}
@@ -1,6 +1,122 @@
 // Mouse.rs - Handling mouse events
+use crate::client::SizeHints;
+use crate::geometry::{Direction, Rect};
+use std::convert::TryFrom;
 use xcb::{ffi, Event, Reply};
 
+// What a drag does, decided when the button is first pressed
+#[derive(Clone, Copy, Default)]
+pub enum Drag {
+    // Moving or resizing the window freely
+    #[default]
+    Free,
+    // Moving the boundary on one edge of a tiled window, from where the pointer last was
+    Split {
+        edge: Direction,
+        last: (i64, i64),
+    },
+    // Picking up a tiled window, with a preview of where it will be dropped
+    Tile {
+        preview: u32,
+        drop: Option<Drop>,
+    },
+    // Resizing the window from the edge or corner nearest the pointer, the opposite edges
+    // staying where they are
+    Resize {
+        horizontal: Option<Direction>,
+        vertical: Option<Direction>,
+        hints: SizeHints,
+    },
+}
+
+impl Drag {
+    pub fn resize(geo: Rect, x: i64, y: i64, hints: SizeHints) -> Self {
+        // Pick the edges to resize from, by which third of the window the pointer is in
+        let (w, h) = (i64::from(geo.w), i64::from(geo.h));
+        let horizontal = match x - geo.x {
+            dx if dx < w / 3 => Some(Direction::Left),
+            dx if dx >= w - w / 3 => Some(Direction::Right),
+            _ => None,
+        };
+        let vertical = match y - geo.y {
+            dy if dy < h / 3 => Some(Direction::Up),
+            dy if dy >= h - h / 3 => Some(Direction::Down),
+            _ => None,
+        };
+        // Right in the middle, go with whichever edge is closest
+        let (horizontal, vertical) = match (horizontal, vertical) {
+            (None, None) => {
+                let sides = [
+                    (Direction::Left, x - geo.x),
+                    (Direction::Right, geo.right() - x),
+                    (Direction::Up, y - geo.y),
+                    (Direction::Down, geo.bottom() - y),
+                ];
+                match sides.iter().copied().min_by_key(|(_, d)| *d).unwrap().0 {
+                    edge @ (Direction::Left | Direction::Right) => (Some(edge), None),
+                    edge => (None, Some(edge)),
+                }
+            }
+            edges => edges,
+        };
+        Drag::Resize {
+            horizontal,
+            vertical,
+            hints,
+        }
+    }
+}
+
+// Where a tiled window goes when it's dropped
+#[derive(Clone, Copy)]
+pub enum Drop {
+    // Swap places with another window
+    Swap(u32),
+    // Go beside another window, on one of its edges
+    Beside(u32, Direction),
+    // Go over to the workspace on another monitor
+    Monitor(usize),
+}
+
+pub fn resize_edges(
+    geo: Rect,
+    horizontal: Option<Direction>,
+    vertical: Option<Direction>,
+    delta: (i64, i64),
+    hints: SizeHints,
+) -> Rect {
+    // Move the edges being dragged by how far the pointer has gone, keeping the opposite edges
+    // where they were and the size within the client's hints
+    let (w, h) = (i64::from(geo.w), i64::from(geo.h));
+    let w = match horizontal {
+        Some(Direction::Left) => w - delta.0,
+        Some(_) => w + delta.0,
+        None => w,
+    };
+    let h = match vertical {
+        Some(Direction::Up) => h - delta.1,
+        Some(_) => h + delta.1,
+        None => h,
+    };
+    let (w, h) = hints.constrain(w, h);
+    let x = if horizontal == Some(Direction::Left) {
+        geo.right() - w
+    } else {
+        geo.x
+    };
+    let y = if vertical == Some(Direction::Up) {
+        geo.bottom() - h
+    } else {
+        geo.y
+    };
+    Rect::new(
+        x,
+        y,
+        u32::try_from(w).unwrap_or(1),
+        u32::try_from(h).unwrap_or(1),
+    )
+}
+
 // Mouse move event struct
 #[derive(Default)]
 #[allow(clippy::module_name_repetitions)]
@@ -10,6 +126,7 @@ pub struct MouseInfo {
     pub child: u32,
     pub detail: u8,
     pub geo: Option<(i64, i64, u32, u32)>,
+    pub drag: Drag,
 }
 
 impl MouseInfo {
@@ -31,6 +148,7 @@ impl MouseInfo {
                     u32::from(geo.height()),
                 )
             }),
+            drag: Drag::Free,
         }
     }
 
@@ -42,6 +160,7 @@ impl MouseInfo {
             child: event.child(),
             detail: event.detail(),
             geo: None,
+            drag: Drag::Free,
         }
     }
 }
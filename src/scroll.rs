@@ -0,0 +1,279 @@
+// Scroll.rs - Handles scrolling tiling, where windows sit in columns on an endless strip
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+use crate::geometry::{Direction, Rect};
+use crate::layout::{Layout, Masters, Message};
+
+// Widths that a column can be cycled through, as a share of the monitor
+const WIDTHS: [f64; 3] = [1.0 / 3.0, 0.5, 2.0 / 3.0];
+// Width that new columns start out at
+const DEFAULT_WIDTH: f64 = 0.5;
+// Narrowest and widest a column can be resized to
+const MIN_WIDTH: f64 = 0.1;
+const MAX_WIDTH: f64 = 1.0;
+
+// Column of windows, one above the other, at its preferred width
+struct Column {
+    windows: Vec<u32>,
+    width: f64,
+}
+
+impl Column {
+    fn new(window: u32) -> Self {
+        // Create a column holding a single window
+        Self {
+            windows: vec![window],
+            width: DEFAULT_WIDTH,
+        }
+    }
+}
+
+// Scrolling layout, where the monitor is a viewport onto a strip of columns
+pub struct Scrolling {
+    columns: Vec<Column>,
+    focus: Option<u32>,
+    offset: i64,
+}
+
+impl Scrolling {
+    pub fn new() -> Self {
+        // Create an empty strip, scrolled to the start
+        Self {
+            columns: vec![],
+            focus: None,
+            offset: 0,
+        }
+    }
+
+    fn windows(&self) -> Vec<u32> {
+        // Get every window on the strip, from left to right
+        self.columns
+            .iter()
+            .flat_map(|c| c.windows.iter().copied())
+            .collect()
+    }
+
+    fn find(&self, window: u32) -> Option<(usize, usize)> {
+        // Find the column a window is in, and its place in that column
+        self.columns.iter().enumerate().find_map(|(col, c)| {
+            let row = c.windows.iter().position(|&w| w == window)?;
+            Some((col, row))
+        })
+    }
+
+    fn remove(&mut self, window: u32) {
+        // Take a window off the strip, along with its column if that leaves it empty
+        let Some((col, row)) = self.find(window) else {
+            return;
+        };
+        self.columns[col].windows.remove(row);
+        if self.columns[col].windows.is_empty() {
+            self.columns.remove(col);
+        }
+        if self.focus == Some(window) {
+            self.focus = None;
+        }
+    }
+
+    fn scroll_to(&mut self, col: usize, width: u32) {
+        // Scroll the least distance needed to bring a column into view
+        let left: i64 = self.columns[..col]
+            .iter()
+            .map(|c| column_width(c, width))
+            .sum();
+        let right = left + column_width(&self.columns[col], width);
+        if right - self.offset > i64::from(width) {
+            self.offset = right - i64::from(width);
+        }
+        if left < self.offset {
+            self.offset = left;
+        }
+    }
+
+    fn move_window(&mut self, focus: u32, direction: Direction) -> bool {
+        // Move a whole column along the strip, or a window up and down its column
+        let Some((col, row)) = self.find(focus) else {
+            return false;
+        };
+        match direction {
+            Direction::Left if col > 0 => self.columns.swap(col, col - 1),
+            Direction::Right if col + 1 < self.columns.len() => self.columns.swap(col, col + 1),
+            Direction::Up if row > 0 => self.columns[col].windows.swap(row, row - 1),
+            Direction::Down if row + 1 < self.columns[col].windows.len() => {
+                self.columns[col].windows.swap(row, row + 1);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn consume(&mut self, focus: u32) -> bool {
+        // Pull the top window of the next column along into the focused window's column
+        let Some((col, _)) = self.find(focus) else {
+            return false;
+        };
+        if col + 1 >= self.columns.len() {
+            return false;
+        }
+        let window = self.columns[col + 1].windows.remove(0);
+        if self.columns[col + 1].windows.is_empty() {
+            self.columns.remove(col + 1);
+        }
+        self.columns[col].windows.push(window);
+        true
+    }
+
+    fn expel(&mut self, focus: u32) -> bool {
+        // Push the focused window out into a column of its own, just after its old one
+        let Some((col, row)) = self.find(focus) else {
+            return false;
+        };
+        if self.columns[col].windows.len() < 2 {
+            return false;
+        }
+        self.columns[col].windows.remove(row);
+        self.columns.insert(col + 1, Column::new(focus));
+        true
+    }
+
+    fn cycle_width(&mut self, focus: u32) -> bool {
+        // Move the focused column on to the next preset width, wrapping around
+        let Some((col, _)) = self.find(focus) else {
+            return false;
+        };
+        let column = &mut self.columns[col];
+        column.width = WIDTHS
+            .iter()
+            .copied()
+            .find(|&w| w > column.width + f64::EPSILON)
+            .unwrap_or(WIDTHS[0]);
+        true
+    }
+}
+
+impl Layout for Scrolling {
+    fn name(&self) -> &'static str {
+        "scrolling"
+    }
+
+    fn towards(&self, window: u32, direction: Direction) -> Option<u32> {
+        // Step into the next column along, even when it's scrolled out of view, at about the
+        // same height as the window
+        let (col, row) = self.find(window)?;
+        let next = match direction {
+            Direction::Left => col.checked_sub(1)?,
+            Direction::Right => col + 1,
+            Direction::Up | Direction::Down => return None,
+        };
+        let windows = &self.columns.get(next)?.windows;
+        windows.get(row.min(windows.len() - 1)).copied()
+    }
+
+    fn resizes(&self, _master: bool, edge: Direction) -> bool {
+        // Columns only change width
+        matches!(edge, Direction::Left | Direction::Right)
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        // Catch up with windows that came or went without being inserted or removed
+        for window in self.windows() {
+            if !windows.contains(&window) {
+                self.remove(window);
+            }
+        }
+        let known = self.windows();
+        for &window in windows {
+            if !known.contains(&window) {
+                self.columns.push(Column::new(window));
+            }
+        }
+        // Keep the focused column in view, and don't scroll past the end of the strip
+        let total: i64 = self.columns.iter().map(|c| column_width(c, area.w)).sum();
+        self.offset = self.offset.min(total - i64::from(area.w)).max(0);
+        if let Some((col, _)) = self.focus.and_then(|f| self.find(f)) {
+            self.scroll_to(col, area.w);
+        }
+        // Lay the columns out side by side, starting from wherever the viewport has scrolled to
+        let mut x = area.x - self.offset;
+        let mut arranged = vec![];
+        for column in &self.columns {
+            let width = column_width(column, area.w);
+            let rows = Rect::new(x, area.y, width as u32, area.h).rows(column.windows.len());
+            arranged.extend(column.windows.iter().copied().zip(rows));
+            x += width;
+        }
+        arranged
+    }
+
+    fn insert(&mut self, window: u32, target: Option<u32>) {
+        // Give a new window a column of its own, just after the target's column
+        let col = target
+            .and_then(|t| self.find(t))
+            .map_or(self.columns.len(), |(col, _)| col + 1);
+        self.columns.insert(col, Column::new(window));
+        self.focus = Some(window);
+    }
+
+    fn handle(&mut self, message: Message, focus: u32) -> bool {
+        // Carry out an action on the strip, around the focused window
+        self.focus = Some(focus);
+        match message {
+            Message::Move(direction) => self.move_window(focus, direction),
+            Message::Resize(delta) => {
+                let Some((col, _)) = self.find(focus) else {
+                    return false;
+                };
+                let column = &mut self.columns[col];
+                column.width = (column.width + delta).clamp(MIN_WIDTH, MAX_WIDTH);
+                true
+            }
+            Message::Consume => self.consume(focus),
+            Message::Expel => self.expel(focus),
+            Message::CycleWidth => self.cycle_width(focus),
+            _ => false,
+        }
+    }
+
+    fn swap(&mut self, window: u32, other: u32) {
+        // Swap the places of two windows, wherever they are on the strip
+        let (Some((col, row)), Some((other_col, other_row))) =
+            (self.find(window), self.find(other))
+        else {
+            return;
+        };
+        self.columns[col].windows[row] = other;
+        self.columns[other_col].windows[other_row] = window;
+    }
+
+    fn place_beside(&mut self, window: u32, target: u32, edge: Direction) {
+        // Give the window a column of its own either side of the target, or put it in the
+        // target's column, above or below it
+        self.remove(window);
+        let Some((col, row)) = self.find(target) else {
+            self.columns.push(Column::new(window));
+            return;
+        };
+        match edge {
+            Direction::Left => self.columns.insert(col, Column::new(window)),
+            Direction::Right => self.columns.insert(col + 1, Column::new(window)),
+            Direction::Up => self.columns[col].windows.insert(row, window),
+            Direction::Down => self.columns[col].windows.insert(row + 1, window),
+        }
+        self.focus = Some(window);
+    }
+
+    fn focus(&mut self, window: u32) -> bool {
+        // Scroll over to the newly focused window, if it isn't already in view
+        let changed = self.focus != Some(window);
+        self.focus = Some(window);
+        changed
+    }
+}
+
+fn column_width(column: &Column, width: u32) -> i64 {
+    // Get the width of a column in pixels, on a monitor of a given width
+    (f64::from(width) * column.width) as i64
+}
@@ -0,0 +1,48 @@
+// Stack.rs - Handles the stacking order of windows, and the layers they're kept in
+
+// Layers that windows are kept in, from the bottom of the screen up
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Desktop,
+    Below,
+    Normal,
+    Floating,
+    Above,
+    Dock,
+    Fullscreen,
+    Notification,
+}
+
+// Stack struct that holds the order windows were raised in, from the bottom up
+#[derive(Default)]
+pub struct Stack {
+    windows: Vec<u32>,
+}
+
+impl Stack {
+    pub fn push(&mut self, window: u32) {
+        // Put a new window on top of the stack
+        self.remove(window);
+        self.windows.push(window);
+    }
+
+    pub fn remove(&mut self, window: u32) {
+        // Take a window off the stack
+        self.windows.retain(|&w| w != window);
+    }
+
+    pub fn raise(&mut self, window: u32) {
+        // Bring a window to the top of the stack, if it's on it
+        if self.windows.contains(&window) {
+            self.push(window);
+        }
+    }
+
+    pub fn order(&self, layer: impl Fn(u32) -> Layer) -> Vec<(u32, Layer)> {
+        // Get the windows from the bottom up, each layer above the last, and windows in the
+        // same layer in the order they were raised
+        let mut order: Vec<(u32, Layer)> = self.windows.iter().map(|&w| (w, layer(w))).collect();
+        order.sort_by_key(|&(_, layer)| layer);
+        order
+    }
+}
@@ -0,0 +1,61 @@
+// Startup.rs - Tracks startup notification sequences for launched programs
+use std::time::{Duration, Instant};
+
+// How long to wait for a launched program before giving up on it
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Launch struct that holds information about a program that is starting up
+pub struct Launch {
+    pub id: String,
+    pub workspace: usize,
+    pub started: Instant,
+}
+
+impl Launch {
+    pub fn new(id: String, workspace: usize) -> Self {
+        // Create a new launch, from the workspace it was started on
+        Self {
+            id,
+            workspace,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        // Check if this program has taken too long to start
+        self.started.elapsed() >= STARTUP_TIMEOUT
+    }
+}
+
+pub fn parse(message: &str) -> Option<(&str, String)> {
+    // Turn a startup notification message (e.g. `remove: ID=foo`) into its type and ID
+    let (kind, fields) = message.split_once(':')?;
+    let mut chars = fields.chars();
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut in_value = false;
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_value => value.extend(chars.next()),
+            '"' if in_value => quoted = !quoted,
+            '=' if !in_value => in_value = true,
+            ' ' if !quoted => {
+                // End of a KEY=VALUE pair
+                if key == "ID" {
+                    return Some((kind, value));
+                }
+                key.clear();
+                value.clear();
+                in_value = false;
+            }
+            _ if in_value => value.push(c),
+            _ => key.push(c),
+        }
+    }
+    if key == "ID" {
+        Some((kind, value))
+    } else {
+        None
+    }
+}
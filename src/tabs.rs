@@ -0,0 +1,158 @@
+// Tabs.rs - Handles the tab strips drawn above tabbed and stacked layouts
+#![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+use crate::geometry::Rect;
+
+// Space left between the edge of a tab and its title
+const PADDING: i64 = 6;
+// How far down a tab the baseline of its title sits
+const BASELINE: i64 = 14;
+
+// Strip struct that holds the window the tabs of a workspace are drawn on
+pub struct Strip {
+    pub window: u32,
+    gc: u32,
+    area: Rect,
+    tabs: Vec<(u32, Rect)>,
+}
+
+impl Strip {
+    pub fn new(conn: &xcb::Connection, root: u32, font: &str) -> Self {
+        // Create the window for a tab strip, which StarWM looks after itself
+        let window = conn.generate_id();
+        xcb::create_window(
+            conn,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            xcb::WINDOW_CLASS_INPUT_OUTPUT as u16,
+            xcb::COPY_FROM_PARENT,
+            &[
+                (xcb::CW_OVERRIDE_REDIRECT, 1),
+                (
+                    xcb::CW_EVENT_MASK,
+                    xcb::EVENT_MASK_EXPOSURE | xcb::EVENT_MASK_BUTTON_PRESS,
+                ),
+            ],
+        );
+        // Titles are drawn with a core font, which the graphics context holds onto
+        let f = conn.generate_id();
+        xcb::open_font(conn, f, font);
+        let gc = conn.generate_id();
+        xcb::create_gc(
+            conn,
+            gc,
+            window,
+            &[(xcb::GC_FONT, f), (xcb::GC_GRAPHICS_EXPOSURES, 0)],
+        );
+        xcb::close_font(conn, f);
+        Self {
+            window,
+            gc,
+            area: Rect::default(),
+            tabs: vec![],
+        }
+    }
+
+    pub fn place(&mut self, conn: &xcb::Connection, tabs: Vec<(u32, Rect)>) {
+        // Cover the tabs of a layout, where it's stacked with the windows it labels
+        let left = tabs.iter().map(|(_, t)| t.x).min().unwrap_or_default();
+        let top = tabs.iter().map(|(_, t)| t.y).min().unwrap_or_default();
+        let right = tabs
+            .iter()
+            .map(|(_, t)| t.right())
+            .max()
+            .unwrap_or_default();
+        let bottom = tabs
+            .iter()
+            .map(|(_, t)| t.bottom())
+            .max()
+            .unwrap_or_default();
+        self.area = Rect::new(
+            left,
+            top,
+            (right - left).max(1) as u32,
+            (bottom - top).max(1) as u32,
+        );
+        self.tabs = tabs;
+        xcb::configure_window(
+            conn,
+            self.window,
+            &[
+                (xcb::CONFIG_WINDOW_X as u16, self.area.x as u32),
+                (xcb::CONFIG_WINDOW_Y as u16, self.area.y as u32),
+                (xcb::CONFIG_WINDOW_WIDTH as u16, self.area.w),
+                (xcb::CONFIG_WINDOW_HEIGHT as u16, self.area.h),
+            ],
+        );
+        xcb::map_window(conn, self.window);
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = u32> + '_ {
+        // Iterate through the windows that have a tab, in the order they're drawn
+        self.tabs.iter().map(|(window, _)| *window)
+    }
+
+    pub fn tab_at(&self, x: i64, y: i64) -> Option<u32> {
+        // Find the window whose tab is under a point on the screen
+        self.tabs
+            .iter()
+            .find(|(_, tab)| tab.contains(x, y))
+            .map(|(window, _)| *window)
+    }
+
+    pub fn draw(&self, conn: &xcb::Connection, labels: &[(String, u32)], text: u32) {
+        // Fill in each tab and write its title, in order so long titles are covered up by the
+        // next tab along
+        for ((_, tab), (title, colour)) in self.tabs.iter().zip(labels) {
+            let x = tab.x - self.area.x;
+            let y = tab.y - self.area.y;
+            xcb::change_gc(
+                conn,
+                self.gc,
+                &[(xcb::GC_FOREGROUND, *colour), (xcb::GC_BACKGROUND, *colour)],
+            );
+            let rectangle = xcb::Rectangle::new(x as i16, y as i16, tab.w as u16, tab.h as u16);
+            xcb::poly_fill_rectangle(conn, self.window, self.gc, &[rectangle]);
+            xcb::change_gc(conn, self.gc, &[(xcb::GC_FOREGROUND, text)]);
+            xcb::image_text_8(
+                conn,
+                self.window,
+                self.gc,
+                (x + PADDING) as i16,
+                (y + BASELINE) as i16,
+                &printable(title),
+            );
+        }
+    }
+
+    pub fn hide(&self, conn: &xcb::Connection) {
+        // Hide the strip while its workspace isn't shown
+        xcb::unmap_window(conn, self.window);
+    }
+
+    pub fn destroy(&self, conn: &xcb::Connection) {
+        // Get rid of the strip, once its layout no longer has tabs
+        xcb::free_gc(conn, self.gc);
+        xcb::destroy_window(conn, self.window);
+    }
+}
+
+fn printable(title: &str) -> String {
+    // Core fonts only cover ASCII reliably, and only take 255 characters at a time
+    title
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c
+            } else {
+                '?'
+            }
+        })
+        .take(255)
+        .collect()
+}
@@ -0,0 +1,421 @@
+// Tree.rs - Handles manual tiling, where windows are arranged in a tree of split containers
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+use crate::geometry::{Direction, Rect};
+use crate::layout::{Layout, Masters, Message};
+
+// Smallest share of a container that any one child can be shrunk to
+const MIN_SHARE: f64 = 0.1;
+
+// Which way a container lays out its children
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Axis {
+    // Side by side
+    Horizontal,
+    // One above the other
+    Vertical,
+}
+
+impl Axis {
+    fn of(direction: Direction) -> Self {
+        // Get the axis that a direction runs along
+        match direction {
+            Direction::Left | Direction::Right => Axis::Horizontal,
+            Direction::Up | Direction::Down => Axis::Vertical,
+        }
+    }
+
+    fn flip(self) -> Self {
+        // Get the other axis
+        match self {
+            Axis::Horizontal => Axis::Vertical,
+            Axis::Vertical => Axis::Horizontal,
+        }
+    }
+}
+
+// Something in the tree, either a window or a container of more nodes
+enum Node {
+    Window(u32),
+    Split(Split),
+}
+
+// Container that shares its area between its children, by weight
+struct Split {
+    axis: Axis,
+    children: Vec<Node>,
+    weights: Vec<f64>,
+}
+
+impl Split {
+    fn new(axis: Axis) -> Self {
+        // Create an empty container
+        Self {
+            axis,
+            children: vec![],
+            weights: vec![],
+        }
+    }
+
+    fn find(&self, window: u32) -> Option<Vec<usize>> {
+        // Find the path of child indexes that leads to a window
+        for (idx, child) in self.children.iter().enumerate() {
+            match child {
+                Node::Window(w) if *w == window => return Some(vec![idx]),
+                Node::Split(split) => {
+                    if let Some(mut path) = split.find(window) {
+                        path.insert(0, idx);
+                        return Some(path);
+                    }
+                }
+                Node::Window(_) => (),
+            }
+        }
+        None
+    }
+
+    fn container_mut(&mut self, path: &[usize]) -> &mut Split {
+        // Follow a path of child indexes down to a container
+        match path.split_first() {
+            None => self,
+            Some((&idx, rest)) => match &mut self.children[idx] {
+                Node::Split(split) => split.container_mut(rest),
+                Node::Window(_) => unreachable!("paths only pass through containers"),
+            },
+        }
+    }
+
+    fn windows(&self, found: &mut Vec<u32>) {
+        // Collect every window within this container
+        for child in &self.children {
+            match child {
+                Node::Window(window) => found.push(*window),
+                Node::Split(split) => split.windows(found),
+            }
+        }
+    }
+
+    fn insert(&mut self, idx: usize, node: Node, weight: f64) {
+        // Add a child at a position
+        self.children.insert(idx, node);
+        self.weights.insert(idx, weight);
+    }
+
+    fn remove(&mut self, idx: usize) -> Node {
+        // Take a child out
+        self.weights.remove(idx);
+        self.children.remove(idx)
+    }
+
+    fn average_weight(&self) -> f64 {
+        // Get the weight a new child should have to get an even share
+        if self.weights.is_empty() {
+            1.0
+        } else {
+            self.weights.iter().sum::<f64>() / self.weights.len() as f64
+        }
+    }
+
+    fn arrange(&self, area: Rect, arranged: &mut Vec<(u32, Rect)>) {
+        // Share an area between the children of this container, in proportion to their weights
+        let total: f64 = self.weights.iter().sum();
+        let length = match self.axis {
+            Axis::Horizontal => area.w,
+            Axis::Vertical => area.h,
+        };
+        let mut rest = area;
+        let mut used = 0.0;
+        let mut start = 0;
+        for (child, weight) in self.children.iter().zip(&self.weights) {
+            // Work from running totals, so rounding never leaves a gap at the end
+            used += weight;
+            let end = (f64::from(length) * used / total).round() as u32;
+            let (part, remainder) = match self.axis {
+                Axis::Horizontal => rest.split_x(end - start),
+                Axis::Vertical => rest.split_y(end - start),
+            };
+            start = end;
+            rest = remainder;
+            match child {
+                Node::Window(window) => arranged.push((*window, part)),
+                Node::Split(split) => split.arrange(part, arranged),
+            }
+        }
+    }
+
+    fn flatten(&mut self) {
+        // Remove containers that hold a single child, or that split the same way as this one
+        let children = std::mem::take(&mut self.children);
+        let weights = std::mem::take(&mut self.weights);
+        for (child, weight) in children.into_iter().zip(weights) {
+            match child {
+                Node::Split(mut split) => {
+                    split.flatten();
+                    if split.children.len() == 1 {
+                        self.children.extend(split.children);
+                        self.weights.push(weight);
+                    } else if split.axis == self.axis {
+                        let total: f64 = split.weights.iter().sum();
+                        self.weights
+                            .extend(split.weights.iter().map(|w| weight * w / total));
+                        self.children.extend(split.children);
+                    } else {
+                        self.children.push(Node::Split(split));
+                        self.weights.push(weight);
+                    }
+                }
+                window @ Node::Window(_) => {
+                    self.children.push(window);
+                    self.weights.push(weight);
+                }
+            }
+        }
+    }
+
+    fn rotate(&mut self) {
+        // Turn this container and everything in it a quarter turn clockwise
+        if self.axis == Axis::Vertical {
+            self.children.reverse();
+            self.weights.reverse();
+        }
+        self.axis = self.axis.flip();
+        for child in &mut self.children {
+            if let Node::Split(split) = child {
+                split.rotate();
+            }
+        }
+    }
+}
+
+// Tree layout, where windows go wherever they're put, next to the focused window
+pub struct Tree {
+    root: Split,
+    preselect: Option<Direction>,
+    arranged: Vec<(u32, Rect)>,
+}
+
+impl Tree {
+    pub fn new() -> Self {
+        // Create an empty tree, that starts off splitting side by side
+        Self {
+            root: Split::new(Axis::Horizontal),
+            preselect: None,
+            arranged: vec![],
+        }
+    }
+
+    fn windows(&self) -> Vec<u32> {
+        // Get every window in the tree
+        let mut found = vec![];
+        self.root.windows(&mut found);
+        found
+    }
+
+    fn remove(&mut self, window: u32) {
+        // Take a window out of the tree, tidying up the container it leaves behind
+        let Some(path) = self.root.find(window) else {
+            return;
+        };
+        let (&idx, parent) = path.split_last().unwrap();
+        let container = self.root.container_mut(parent);
+        container.remove(idx);
+        // A container with one child left, or none, is no longer needed
+        if container.children.len() < 2 {
+            if let Some((&idx, grandparent)) = parent.split_last() {
+                let container = self.root.container_mut(grandparent);
+                let weight = container.weights[idx];
+                if let Node::Split(mut split) = container.remove(idx) {
+                    if let Some(child) = split.children.pop() {
+                        container.insert(idx, child, weight);
+                    }
+                }
+            }
+        }
+        // Nor is a container that fills the whole tree
+        if self.root.children.len() == 1 {
+            if let Node::Split(_) = self.root.children[0] {
+                if let Node::Split(split) = self.root.remove(0) {
+                    self.root = split;
+                }
+            }
+        }
+    }
+
+    fn swap(&mut self, window: u32, other: u32) {
+        // Swap the places of two windows
+        let (Some(first), Some(second)) = (self.root.find(window), self.root.find(other)) else {
+            return;
+        };
+        for (path, window) in [(first, other), (second, window)] {
+            let (&idx, parent) = path.split_last().unwrap();
+            self.root.container_mut(parent).children[idx] = Node::Window(window);
+        }
+    }
+
+    fn resize(&mut self, window: u32, delta: f64) -> bool {
+        // Grow or shrink a window within the nearest container it shares with something else
+        let Some(path) = self.root.find(window) else {
+            return false;
+        };
+        for depth in (0..path.len()).rev() {
+            let container = self.root.container_mut(&path[..depth]);
+            if container.children.len() < 2 {
+                continue;
+            }
+            let idx = path[depth];
+            let total: f64 = container.weights.iter().sum();
+            let others = total - container.weights[idx];
+            let share = (container.weights[idx] / total + delta).clamp(MIN_SHARE, 1.0 - MIN_SHARE);
+            for (other, weight) in container.weights.iter_mut().enumerate() {
+                if other != idx {
+                    *weight *= (1.0 - share) * total / others;
+                }
+            }
+            container.weights[idx] = share * total;
+            return true;
+        }
+        false
+    }
+}
+
+impl Layout for Tree {
+    fn name(&self) -> &'static str {
+        "tree"
+    }
+
+    fn resizes(&self, _master: bool, _edge: Direction) -> bool {
+        // Every window can grow within its container
+        true
+    }
+
+    fn arrange(&mut self, windows: &[u32], area: Rect, _masters: Masters) -> Vec<(u32, Rect)> {
+        // Catch up with windows that came or went without being inserted or removed
+        for window in self.windows() {
+            if !windows.contains(&window) {
+                self.remove(window);
+            }
+        }
+        let known = self.windows();
+        for &window in windows {
+            if !known.contains(&window) {
+                self.insert(window, None);
+            }
+        }
+        let mut arranged = vec![];
+        self.root.arrange(area, &mut arranged);
+        self.arranged.clone_from(&arranged);
+        arranged
+    }
+
+    fn insert(&mut self, window: u32, target: Option<u32>) {
+        // Put a new window next to the target, on the preselected side if there is one
+        let Some(path) = target.and_then(|t| self.root.find(t)) else {
+            let weight = self.root.average_weight();
+            let end = self.root.children.len();
+            self.root.insert(end, Node::Window(window), weight);
+            return;
+        };
+        let (&idx, parent) = path.split_last().unwrap();
+        let container = self.root.container_mut(parent);
+        let Some(direction) = self.preselect.take() else {
+            // Nothing preselected, so just go after the target
+            let weight = container.average_weight();
+            container.insert(idx + 1, Node::Window(window), weight);
+            return;
+        };
+        let after = matches!(direction, Direction::Right | Direction::Down);
+        let axis = Axis::of(direction);
+        if container.axis == axis {
+            // Already split the right way, so share the target's space with it
+            container.weights[idx] /= 2.0;
+            let weight = container.weights[idx];
+            container.insert(idx + usize::from(after), Node::Window(window), weight);
+        } else {
+            // Split the target in two, the other way
+            let weight = container.weights[idx];
+            let target = container.remove(idx);
+            let mut split = Split::new(axis);
+            split.insert(0, target, 1.0);
+            split.insert(usize::from(after), Node::Window(window), 1.0);
+            container.insert(idx, Node::Split(split), weight);
+        }
+    }
+
+    fn handle(&mut self, message: Message, focus: u32) -> bool {
+        // Carry out an action on the tree, around the focused window
+        match message {
+            Message::Split(axis) => {
+                // Wrap the focused window in a new container, for the next window to go in
+                let Some(path) = self.root.find(focus) else {
+                    return false;
+                };
+                let (&idx, parent) = path.split_last().unwrap();
+                let container = self.root.container_mut(parent);
+                if container.children.len() == 1 {
+                    container.axis = axis;
+                } else {
+                    let weight = container.weights[idx];
+                    let mut split = Split::new(axis);
+                    split.insert(0, container.remove(idx), 1.0);
+                    container.insert(idx, Node::Split(split), weight);
+                }
+                false
+            }
+            Message::Preselect(direction) => {
+                // Choose the side of the focused window that the next window goes, or unset it
+                self.preselect = if self.preselect == Some(direction) {
+                    None
+                } else {
+                    Some(direction)
+                };
+                false
+            }
+            Message::Move(direction) => {
+                // Swap places with the nearest window in a direction
+                let Some(&(_, here)) = self.arranged.iter().find(|(w, _)| *w == focus) else {
+                    return false;
+                };
+                let nearest = self
+                    .arranged
+                    .iter()
+                    .filter(|(w, _)| *w != focus)
+                    .filter_map(|(w, geo)| Some((*w, here.distance_towards(geo, direction)?)))
+                    .min_by_key(|(_, distance)| *distance);
+                if let Some((other, _)) = nearest {
+                    self.swap(focus, other);
+                    return true;
+                }
+                false
+            }
+            Message::Resize(delta) => self.resize(focus, delta),
+            Message::Flatten => {
+                self.root.flatten();
+                true
+            }
+            Message::Rotate => {
+                // Turn the container holding the focused window
+                let Some(path) = self.root.find(focus) else {
+                    return false;
+                };
+                self.root.container_mut(&path[..path.len() - 1]).rotate();
+                true
+            }
+            Message::Consume | Message::Expel | Message::CycleWidth => false,
+        }
+    }
+
+    fn swap(&mut self, window: u32, other: u32) {
+        Tree::swap(self, window, other);
+    }
+
+    fn place_beside(&mut self, window: u32, target: u32, edge: Direction) {
+        // Take the window out, and put it back in as though the edge had been preselected
+        self.remove(window);
+        let preselect = self.preselect.replace(edge);
+        self.insert(window, Some(target));
+        self.preselect = preselect;
+    }
+}
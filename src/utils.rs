@@ -1,4 +1,8 @@
 // Utils.rs - Contains useful tools that help make code concise throughout.
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::os::unix::io::RawFd;
+use std::time::Duration;
 
 // Helper macro for creating strings
 #[macro_export]
@@ -8,7 +12,7 @@ macro_rules! st {
     };
 }
 
-// Helper macro for running commands
+// Helper macro for running commands, optionally with a startup notification ID
 #[macro_export]
 macro_rules! cmd {
     ($cmd:expr) => {{
@@ -21,4 +25,43 @@ macro_rules! cmd {
             );
         });
     }};
+    ($cmd:expr, $startup_id:expr) => {{
+        let startup_id = $startup_id;
+        std::thread::spawn(move || {
+            std::mem::drop(
+                std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg($cmd)
+                    .env("DESKTOP_STARTUP_ID", startup_id)
+                    .status(),
+            );
+        });
+    }};
+}
+
+pub fn wait_readable(fd: RawFd, timeout: Duration) {
+    // Block until a file descriptor has data to read, or until the timeout passes
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe {
+        libc::poll(
+            std::ptr::addr_of_mut!(pollfd),
+            1,
+            i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX),
+        );
+    }
+}
+
+pub fn hostname() -> String {
+    // Get the name of the machine StarWM is running on
+    let mut buffer = [0_u8; 256];
+    unsafe {
+        libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len());
+    }
+    CStr::from_bytes_until_nul(&buffer)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
 }
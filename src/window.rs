@@ -1,5 +1,9 @@
 // Window.rs - Handles window arrangement and management
+use crate::config::Gaps;
+use crate::geometry::Rect;
 use crate::key::Key;
+use crate::layout::{Floating, Layout, Masters};
+use crate::stack::Stack;
 
 pub const BLACKLIST: [&str; 14] = [
     "_NET_WM_WINDOW_TYPE_DESKTOP",
@@ -20,54 +24,114 @@ pub const BLACKLIST: [&str; 14] = [
 
 // Workspace struct that holds information about a specific workspace
 pub struct Workspace {
+    pub root: u32,
     pub trigger: Key,
-    floating: Vec<u32>,
+    clients: Vec<u32>,
+    pub layout: Box<dyn Layout>,
+    pub masters: Masters,
+    pub gaps: Gaps,
     monocle: Option<u32>,
     pub previous_geometry: Option<(i64, i64, u32, u32)>,
+    pub area: Option<Rect>,
+    pub urgent: bool,
     focus: usize,
+    history: Vec<u32>,
+    pub stack: Stack,
 }
 
 impl Workspace {
-    pub fn new<K: Into<Key>>(trigger: K) -> Self {
-        // Create a new workspace
+    pub fn new<K: Into<Key>>(root: u32, trigger: K, masters: Masters, gaps: Gaps) -> Self {
+        // Create a new workspace, on the screen with this root window
         Self {
+            root,
             trigger: trigger.into(),
-            floating: vec![],
+            clients: vec![],
+            layout: Box::new(Floating),
+            masters,
+            gaps,
             monocle: None,
             previous_geometry: None,
+            area: None,
+            urgent: false,
             focus: 0,
+            history: vec![],
+            stack: Stack::default(),
         }
     }
 
     pub fn add(&mut self, window: u32) {
         // Add window to this workspace
-        self.floating.push(window);
-        self.focus = self.floating.len().saturating_sub(1);
+        self.clients.push(window);
+        self.focus = self.clients.len().saturating_sub(1);
+        self.touch(window);
+        self.stack.push(window);
     }
 
     pub fn remove(&mut self, window: u32) {
         // Remove a window from this workspace
-        self.floating.retain(|&w| w != window);
+        self.clients.retain(|&w| w != window);
+        self.history.retain(|&w| w != window);
+        self.stack.remove(window);
+        // Hand focus back to the window used most recently
+        if let Some(idx) = self.history.iter().find_map(|&w| self.find(w)) {
+            self.focus = idx;
+        }
         // Fix focus if need be
-        if self.focus >= self.floating.len() {
-            self.focus = self.floating.len().saturating_sub(1);
+        if self.focus >= self.clients.len() {
+            self.focus = self.clients.len().saturating_sub(1);
         }
     }
 
     pub fn get_focus(&self) -> Option<u32> {
         // Get the currently focused window
-        Some(*self.floating.get(self.focus)?)
+        Some(*self.clients.get(self.focus)?)
     }
 
     pub fn set_focus(&mut self, window: u32) {
-        // Set the currently focused window
-        self.focus = self.find(window).unwrap();
+        // Set the currently focused window, if it's on this workspace
+        let Some(idx) = self.find(window) else {
+            return;
+        };
+        self.focus = idx;
+        self.touch(window);
+    }
+
+    pub fn peek_focus(&mut self, window: u32) {
+        // Focus a window for now, without it counting as being used
+        if let Some(idx) = self.find(window) {
+            self.focus = idx;
+        }
+    }
+
+    pub fn history(&self) -> &[u32] {
+        // Get the windows of this workspace, from the most recently focused
+        &self.history
+    }
+
+    fn touch(&mut self, window: u32) {
+        // Move a window to the front of the focus history
+        self.history.retain(|&w| w != window);
+        self.history.insert(0, window);
+    }
+
+    pub fn neighbour(&self, forward: bool) -> Option<u32> {
+        // Get the window after (or before) the focused one, wrapping around
+        let count = self.clients.len();
+        if count == 0 {
+            return None;
+        }
+        let idx = if forward {
+            (self.focus + 1) % count
+        } else {
+            (self.focus + count - 1) % count
+        };
+        Some(self.clients[idx])
     }
 
     pub fn set_monocle(&mut self) -> Option<u32> {
         // Set focused to monocle window
         let focus = self.get_focus()?;
-        self.floating.retain(|&w| w != focus);
+        self.clients.retain(|&w| w != focus);
         self.monocle = Some(focus);
         return self.monocle;
     }
@@ -80,14 +144,14 @@ impl Workspace {
     pub fn clear_monocle(&mut self) -> Option<u32> {
         // Clear the monocle
         let monocle = self.monocle?;
-        self.floating.insert(self.focus, monocle);
+        self.clients.insert(self.focus, monocle);
         self.monocle = None;
         Some(monocle)
     }
 
     pub fn show(&self, conn: &xcb::Connection) {
         // Show all windows within this workspace
-        for window in &self.floating {
+        for window in &self.clients {
             xcb::map_window(conn, *window);
         }
         // Show monocled window if need be
@@ -98,7 +162,7 @@ impl Workspace {
 
     pub fn hide(&self, conn: &xcb::Connection) {
         // Hide all windows within this workspace
-        for window in &self.floating {
+        for window in &self.clients {
             xcb::unmap_window(conn, *window);
         }
         // Hide monocled window if need be
@@ -109,11 +173,86 @@ impl Workspace {
 
     pub fn contains(&self, window: u32) -> bool {
         // Check if this workspace contains a window
-        self.floating.contains(&window) || self.monocle == Some(window)
+        self.clients.contains(&window) || self.monocle == Some(window)
+    }
+
+    pub fn clients(&self) -> &[u32] {
+        // Get the windows in this workspace that are laid out, which excludes the monocle
+        &self.clients
+    }
+
+    pub fn promote(&mut self, window: u32) {
+        // Move a window to the front, where layouts put their masters
+        let Some(idx) = self.find(window) else {
+            return;
+        };
+        // Already the master, so swap it with the next window along instead
+        let target = if idx == 0 && self.clients.len() > 1 {
+            self.clients[1]
+        } else {
+            window
+        };
+        let idx = self.find(target).unwrap();
+        self.clients.remove(idx);
+        self.clients.insert(0, target);
+        self.focus = 0;
+    }
+
+    pub fn swap(&mut self, window: u32, other: u32) {
+        // Swap the places of two windows, keeping focus on the first
+        if let (Some(first), Some(second)) = (self.find(window), self.find(other)) {
+            self.clients.swap(first, second);
+            self.focus = second;
+        }
+    }
+
+    pub fn replace(&mut self, window: u32, other: u32) {
+        // Put another window in the place of one of ours
+        if let Some(idx) = self.find(window) {
+            self.clients[idx] = other;
+            if let Some(entry) = self.history.iter_mut().find(|w| **w == window) {
+                *entry = other;
+            }
+            self.stack.remove(window);
+            self.stack.push(other);
+        }
+    }
+
+    pub fn place_beside(&mut self, window: u32, target: u32, after: bool) {
+        // Move a window to just before (or after) another, keeping focus on it
+        let Some(from) = self.find(window) else {
+            return;
+        };
+        self.clients.remove(from);
+        // Without the target, the window goes back where it was
+        let to = self
+            .find(target)
+            .map_or(from, |idx| idx + usize::from(after));
+        self.clients.insert(to, window);
+        self.focus = to;
+    }
+
+    pub fn rotate(&mut self, forward: bool) {
+        // Cycle every window one place along, keeping focus on the same window
+        let focus = self.get_focus();
+        let step = 1.min(self.clients.len());
+        if forward {
+            self.clients.rotate_right(step);
+        } else {
+            self.clients.rotate_left(step);
+        }
+        if let Some(focus) = focus {
+            self.set_focus(focus);
+        }
+    }
+
+    pub fn windows(&self) -> impl Iterator<Item = u32> + '_ {
+        // Iterate through every window in this workspace, including the monocle
+        self.clients.iter().copied().chain(self.monocle)
     }
 
     pub fn find(&self, window: u32) -> Option<usize> {
         // Find this window, returns None if not found, or if in monocle mode
-        self.floating.iter().position(|w| w == &window)
+        self.clients.iter().position(|w| w == &window)
     }
 }
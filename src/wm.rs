@@ -1,9 +1,29 @@
 // Wm.rs - This is where all the magic happens
-#![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-use crate::config::{Config, Handler};
-use crate::key::{get_lookup, Key, SymTable, META, META_SHIFT};
-use crate::mouse::MouseInfo;
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+use crate::client::{Client, SizeHints, SyncCounter, URGENCY_HINT};
+use crate::config::{
+    Config, FocusModel, Gaps, Handler, Raise, Rule, WindowBorder, WorkspaceSwitch,
+};
+use crate::cycle::Cycle;
+use crate::geometry::{Direction, Rect};
+use crate::key::{get_lookup, is_hold_key, Key, SymTable, HOLD_MASK, META, META_SHIFT};
+use crate::layout::{self, Layout, Masters, Message, TAB_HEIGHT};
+use crate::monitor::{get_monitors, Monitor};
+use crate::mouse::{resize_edges, Drag, Drop, MouseInfo};
+use crate::stack::Layer;
+use crate::startup::{self, Launch};
+use crate::tabs::Strip;
+use crate::utils::{hostname, wait_readable};
 use crate::window::{Workspace, BLACKLIST};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
 use xcb::{xproto, Connection};
 
 // Shorthand for an X events
@@ -13,74 +33,235 @@ pub type XKeyEvent<'a> = &'a xcb::KeyPressEvent;
 pub type XEnterEvent<'a> = &'a xcb::EnterNotifyEvent;
 pub type XLeaveEvent<'a> = &'a xcb::LeaveNotifyEvent;
 pub type XButtonPressEvent<'a> = &'a xcb::ButtonPressEvent;
+pub type XButtonReleaseEvent<'a> = &'a xcb::ButtonReleaseEvent;
 pub type XMotionEvent<'a> = &'a xcb::MotionNotifyEvent;
+pub type XPropertyEvent<'a> = &'a xcb::PropertyNotifyEvent;
+pub type XClientMessageEvent<'a> = &'a xcb::ClientMessageEvent;
+pub type XExposeEvent<'a> = &'a xcb::ExposeEvent;
+pub type XAlarmEvent<'a> = &'a xcb::sync::AlarmNotifyEvent;
+
+// Actions that can be requested in a _NET_WM_STATE client message
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+const NET_WM_STATE_TOGGLE: u32 = 2;
+
+// Cursor font glyphs
+const CURSOR_NORMAL: u16 = 68;
+const CURSOR_BUSY: u16 = 150;
+// Cursors shown while resizing from each edge and corner
+const CURSOR_LEFT: u16 = 70;
+const CURSOR_RIGHT: u16 = 96;
+const CURSOR_TOP: u16 = 138;
+const CURSOR_BOTTOM: u16 = 16;
+const CURSOR_TOP_LEFT: u16 = 134;
+const CURSOR_TOP_RIGHT: u16 = 136;
+const CURSOR_BOTTOM_LEFT: u16 = 12;
+const CURSOR_BOTTOM_RIGHT: u16 = 14;
+// Events asked for while a mouse button is held
+const DRAG_EVENTS: u32 =
+    xcb::EVENT_MASK_BUTTON_PRESS | xcb::EVENT_MASK_BUTTON_RELEASE | xcb::EVENT_MASK_POINTER_MOTION;
+
+// How much opacity changes by with each step
+const OPACITY_STEP: f64 = 0.1;
+
+// How much of the area master windows gain or lose with each step, and their limits
+const MASTER_STEP: f64 = 0.05;
+const MASTER_MIN: f64 = 0.1;
+const MASTER_MAX: f64 = 0.9;
+
+// How much gaps grow or shrink by with each step
+const GAP_STEP: u32 = 2;
+
+// How far floating windows are nudged with each step
+const MOVE_STEP: i64 = 40;
+
+// How close to the edge of a tile a drag has to start to move the boundary instead
+const EDGE_MARGIN: i64 = 16;
+// How far into a tile a window can be dropped to go beside it, rather than swap with it
+const DROP_EDGE: f64 = 0.25;
+// Opacity of the preview shown while dragging a tile
+const PREVIEW_OPACITY: f64 = 0.4;
+
+// Width of the overlay listing windows while cycling through them
+const OVERLAY_WIDTH: u32 = 400;
+
+// Keys that trigger each workspace on a screen, along with [Meta]
+const WORKSPACE_KEYS: [&str; 10] = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "0"];
+
+// How long to sleep between checks on timers when X is quiet
+const TICK: Duration = Duration::from_millis(250);
+
+// EWMH hints that StarWM supports, advertised on the root window
+const SUPPORTED: [&str; 14] = [
+    "_NET_SUPPORTED",
+    "_NET_CLIENT_LIST_STACKING",
+    "_NET_WM_STATE",
+    "_NET_WM_STATE_DEMANDS_ATTENTION",
+    "_NET_WM_STATE_FULLSCREEN",
+    "_NET_WM_STATE_ABOVE",
+    "_NET_WM_STATE_BELOW",
+    "_NET_WM_FULLSCREEN_MONITORS",
+    "_NET_WM_STRUT",
+    "_NET_WM_STRUT_PARTIAL",
+    "_NET_WM_PING",
+    "_NET_WM_PID",
+    "_NET_WM_SYNC_REQUEST",
+    "_NET_WM_SYNC_REQUEST_COUNTER",
+];
 
 // Ignore the David Bowie reference, this is the struct that controls X
 pub struct StarMan {
     conn: Connection,
     conf: Config,
     keymap: SymTable,
+    roots: Vec<u32>,
+    randr_base: Option<u8>,
+    sync_base: Option<u8>,
+    monitors: Vec<Monitor>,
+    struts: HashMap<u32, (u32, [u32; 12])>,
     workspaces: Vec<Workspace>,
-    workspace: usize,
+    strips: HashMap<usize, Strip>,
+    monitor: usize,
+    clients: HashMap<u32, Client>,
+    launches: Vec<Launch>,
+    launched: usize,
+    startup_messages: HashMap<u32, Vec<u8>>,
     mouse: Option<MouseInfo>,
+    history: Vec<u32>,
+    cycle: Option<Cycle>,
+    unmanaged: HashMap<u32, (u32, Layer)>,
+    stacked: HashMap<u32, (Vec<u32>, Vec<u32>)>,
+    published: HashMap<u32, &'static str>,
+    atoms: RefCell<HashMap<String, u32>>,
 }
 
 impl StarMan {
     pub fn new() -> Self {
         // Establish connection with X
         let (conn, _) = Connection::connect(None).expect("Failed to connect to X");
-        let setup = conn.get_setup();
-        let screen = setup.roots().next().unwrap();
-        // Set up workspaces
-        let workspaces = vec![
-            // New workspace, triggered on [Meta] + [WORKSPACE NUMBER]
-            Workspace::new((META, "1")),
-            Workspace::new((META, "2")),
-            Workspace::new((META, "3")),
-            Workspace::new((META, "4")),
-            Workspace::new((META, "5")),
-            Workspace::new((META, "6")),
-            Workspace::new((META, "7")),
-            Workspace::new((META, "8")),
-            Workspace::new((META, "9")),
-            Workspace::new((META, "0")),
-        ];
         // Call XInitThreads to.. well.. init threads
         unsafe {
             x11::xlib::XInitThreads();
         }
-
-        // Establish grab for workspace trigger events
+        // Initialise the sync extension, used to resize windows in step with clients
+        let _ = xcb::sync::initialize(&conn, 3, 1).get_reply();
+        let sync_base = conn
+            .get_extension_data(xcb::sync::id())
+            .filter(xcb::QueryExtensionData::present)
+            .map(|data| data.first_event());
+        let randr_base = conn
+            .get_extension_data(xcb::randr::id())
+            .filter(xcb::QueryExtensionData::present)
+            .map(|data| data.first_event());
         let keymap = get_lookup(&conn);
-        for trigger in workspaces.iter().map(|w| &w.trigger) {
-            StarMan::grab_key(&conn, &screen, trigger, &keymap);
-        }
-        // Establish a grab for mouse events
-        StarMan::grab_button(&conn, &screen, 1, META as u16);
-        StarMan::grab_button(&conn, &screen, 1, META_SHIFT as u16);
-        // Set root cursor as normal left pointer
-        StarMan::set_cursor(&conn, &screen, 68);
-        // Establish a grab for notification events
-        StarMan::grab_notify_events(&conn, &screen);
-        // Write buffer to server
-        conn.flush();
-        // Instantiate and return
-        Self {
+        let config = Config::new();
+        // Take over every screen on the display
+        let mut roots = vec![];
+        let mut monitors = vec![];
+        let mut workspaces = vec![];
+        for screen in conn.get_setup().roots() {
+            let root = screen.root();
+            // Set up workspaces, triggered on [Meta] + [WORKSPACE NUMBER]
+            let first = workspaces.len();
+            for key in &WORKSPACE_KEYS {
+                workspaces.push(Workspace::new(
+                    root,
+                    (META, *key),
+                    config.masters,
+                    config.gaps,
+                ));
+            }
+            // Establish grab for workspace trigger events
+            for trigger in workspaces[first..].iter().map(|w| &w.trigger) {
+                StarMan::grab_key(&conn, &screen, trigger, &keymap);
+            }
+            // Establish a grab for mouse events
+            StarMan::grab_button(&conn, &screen, 1, META as u16);
+            StarMan::grab_button(&conn, &screen, 1, META_SHIFT as u16);
+            StarMan::grab_button(&conn, &screen, config.resize_button, META as u16);
+            // Set root cursor as normal left pointer
+            StarMan::set_cursor(&conn, &screen, CURSOR_NORMAL);
+            // Establish a grab for notification events
+            StarMan::grab_notify_events(&conn, &screen);
+            // Listen for outputs being plugged in, unplugged or reconfigured
+            xcb::randr::select_input(
+                &conn,
+                root,
+                (xcb::randr::NOTIFY_MASK_SCREEN_CHANGE
+                    | xcb::randr::NOTIFY_MASK_CRTC_CHANGE
+                    | xcb::randr::NOTIFY_MASK_OUTPUT_CHANGE) as u16,
+            );
+            // Find the monitors that make up the screen, and give each a workspace of its own
+            let size = Rect::new(
+                0,
+                0,
+                u32::from(screen.width_in_pixels()),
+                u32::from(screen.height_in_pixels()),
+            );
+            let mut found = get_monitors(&conn, root, size);
+            found.truncate(WORKSPACE_KEYS.len());
+            for (idx, monitor) in found.iter_mut().enumerate() {
+                monitor.workspace = first + idx;
+                workspaces[first + idx].area = Some(monitor.geometry);
+            }
+            monitors.extend(found);
+            roots.push(root);
+        }
+        // Instantiate
+        let starman = Self {
             keymap,
+            roots,
+            randr_base,
+            sync_base,
+            monitors,
+            struts: HashMap::new(),
             workspaces,
-            workspace: 0,
-            conf: Config::new(),
+            strips: HashMap::new(),
+            monitor: 0,
+            clients: HashMap::new(),
+            launches: vec![],
+            launched: 0,
+            startup_messages: HashMap::new(),
+            conf: config,
             conn,
             mouse: None,
+            history: vec![],
+            cycle: None,
+            unmanaged: HashMap::new(),
+            stacked: HashMap::new(),
+            published: HashMap::new(),
+            atoms: RefCell::new(HashMap::new()),
+        };
+        // Advertise supported hints on every screen
+        let supported: Vec<u32> = SUPPORTED.iter().map(|name| starman.atom(name)).collect();
+        let net_supported = starman.atom("_NET_SUPPORTED");
+        for &root in &starman.roots {
+            starman.set_property32(root, net_supported, xcb::ATOM_ATOM, &supported);
         }
+        // Write buffer to server and return
+        starman.conn.flush();
+        starman
     }
 
     pub fn run(&mut self) {
         // Start event loop
         loop {
-            // Wait for event
-            let event = self.conn.wait_for_event().unwrap();
-            match event.response_type() {
+            // Wait for event, waking up every tick to check on timers
+            let event = if let Some(event) = self.conn.poll_for_event() {
+                event
+            } else {
+                self.conn.has_error().expect("Lost connection to X");
+                self.tick();
+                self.conn.flush();
+                // Ticking waits on replies from X, which reads in any events sent meanwhile
+                let Some(event) = self.conn.poll_for_queued_event() else {
+                    wait_readable(self.conn.as_raw_fd(), TICK);
+                    continue;
+                };
+                event
+            };
+            // Events sent by other clients have the top bit set, so look past it
+            match event.response_type() & !0x80 {
                 // On window map (window appears)
                 xcb::MAP_NOTIFY => {
                     let map_notify: XMapEvent = unsafe { xcb::cast_event(&event) };
@@ -113,7 +294,23 @@ impl StarMan {
                 }
                 // On mouse button release
                 xcb::BUTTON_RELEASE => {
-                    self.mouse = None;
+                    let button_release: XButtonReleaseEvent = unsafe { xcb::cast_event(&event) };
+                    self.button_release_event(button_release);
+                }
+                // On window property change
+                xcb::PROPERTY_NOTIFY => {
+                    let property_notify: XPropertyEvent = unsafe { xcb::cast_event(&event) };
+                    self.property_event(property_notify);
+                }
+                // On message from a client
+                xcb::CLIENT_MESSAGE => {
+                    let client_message: XClientMessageEvent = unsafe { xcb::cast_event(&event) };
+                    self.client_message_event(client_message);
+                }
+                // On part of a window needing to be redrawn
+                xcb::EXPOSE => {
+                    let expose: XExposeEvent = unsafe { xcb::cast_event(&event) };
+                    self.expose_event(expose);
                 }
                 // On key press
                 xcb::KEY_PRESS => {
@@ -121,6 +318,20 @@ impl StarMan {
                     let key_press: XKeyEvent = unsafe { xcb::cast_event(&event) };
                     self.key_event(key_press);
                 }
+                // On key release
+                xcb::KEY_RELEASE => {
+                    let key_release: XKeyEvent = unsafe { xcb::cast_event(&event) };
+                    self.key_release_event(key_release);
+                }
+                // On outputs being added, removed or changed
+                kind if self.is_screen_change(kind) => {
+                    self.screen_change_event();
+                }
+                // On a client catching up with a sync request
+                kind if self.sync_base == Some(kind.wrapping_sub(xcb::sync::ALARM_NOTIFY)) => {
+                    let alarm_notify: XAlarmEvent = unsafe { xcb::cast_event(&event) };
+                    self.alarm_event(alarm_notify);
+                }
                 // Otherwise
                 _ => (),
             }
@@ -129,50 +340,136 @@ impl StarMan {
         }
     }
 
+    fn tick(&mut self) {
+        // Handle anything that needs doing periodically
+        self.check_pings();
+        self.flush_sync_resizes(false);
+        self.expire_launches();
+    }
+
     fn map_event(&mut self, map_notify: XMapEvent) {
-        // Handle window map event
+        // Handle window map event, on the screen it was reported on
         let window = map_notify.window();
+        let root = map_notify.event();
+        // Override-redirect windows, like tab strips and drag previews, look after themselves
+        if map_notify.override_redirect() {
+            return;
+        }
         // Ensure window type isn't on the blacklist
         let kind = self.get_atom_property(window, "_NET_WM_WINDOW_TYPE");
         let kind = xcb::get_atom_name(&self.conn, kind).get_reply().unwrap();
         if BLACKLIST.contains(&kind.name()) {
+            // Docks still get to reserve space at the edges of the screen
+            if kind.name() == "_NET_WM_WINDOW_TYPE_DOCK" {
+                self.add_struts(window, root);
+                self.arrange_visible();
+            }
+            // Desktops, docks and notifications still have a layer to be kept in
+            let layer = match kind.name() {
+                "_NET_WM_WINDOW_TYPE_DESKTOP" => Layer::Desktop,
+                "_NET_WM_WINDOW_TYPE_DOCK" => Layer::Dock,
+                "_NET_WM_WINDOW_TYPE_NOTIFICATION" => Layer::Notification,
+                _ => return,
+            };
+            self.unmanaged.insert(window, (root, layer));
+            self.restack(root);
             return;
         }
         // Ensure that this window isn't already assigned to a workspace
         if self.workspaces.iter().any(|w| w.contains(window)) {
             return;
         }
-        // Add to the workspace
-        self.workspace_mut().add(window);
+        // Add to the workspace it was launched from, next to its focused window, and start
+        // tracking the client
+        let workspace = self
+            .take_launch(window)
+            .filter(|&w| self.workspaces[w].root == root)
+            .unwrap_or_else(|| self.root_workspace(root));
+        let target = self.workspaces[workspace].get_focus();
+        self.workspaces[workspace].layout.insert(window, target);
+        self.workspaces[workspace].add(window);
+        let protocols = self.get_property32(window, self.atom("WM_PROTOCOLS"), xcb::ATOM_ATOM);
+        let class = self.get_string_property(window, xcb::ATOM_WM_CLASS, xcb::ATOM_STRING);
+        let class = class.split('\0').nth(1).unwrap_or_default().to_string();
+        // Windows float if a rule says so, or if they belong to another window, like dialogs
+        let transient = self.get_property32(window, xcb::ATOM_WM_TRANSIENT_FOR, xcb::ATOM_WINDOW);
+        let floating = !transient.is_empty() || self.conf.rule(&class).is_some_and(|r| r.floating);
+        let state = self.get_property32(window, self.atom("_NET_WM_STATE"), xcb::ATOM_ATOM);
+        let mut client = Client::new(class, protocols);
+        client.floating = floating;
+        if state.contains(&self.atom("_NET_WM_STATE_ABOVE")) {
+            client.kept = Some(Layer::Above);
+        } else if state.contains(&self.atom("_NET_WM_STATE_BELOW")) {
+            client.kept = Some(Layer::Below);
+        }
+        client.sync = self.get_sync_counter(window, &client);
+        let monitors = self.atom("_NET_WM_FULLSCREEN_MONITORS");
+        let monitors = self.get_property32(window, monitors, xcb::ATOM_CARDINAL);
+        client.fullscreen_monitors = <[u32; 4]>::try_from(monitors.as_slice()).ok();
+        self.clients.insert(window, client);
         // Grab the events where the cursor leaves and enters the window
-        self.grab_enter_leave(window);
-        // If in monocle, restore layer position
-        if let Some(monocle) = self.workspace().get_monocle() {
-            xcb::configure_window(
-                &self.conn,
-                monocle,
-                &[(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE)],
-            );
+        self.grab_client_events(window);
+        self.grab_clicks(window);
+        if let Some(monitor) = self.monitor_showing(workspace) {
+            // Keep the window on the monitor showing its workspace, and make room for it
+            self.place_window(window, monitor);
+            self.arrange(workspace);
+        }
+        // Windows launched from a workspace on another monitor leave focus alone
+        let focused = workspace == self.current_workspace();
+        if self.monitor_showing(workspace).is_none() {
+            // Launched from a workspace that is no longer visible, so hide it there
+            xcb::unmap_window(&self.conn, window);
+        } else if let (true, Some(monocle)) = (focused, self.workspace().get_monocle()) {
+            // If in monocle, its layer keeps it on top, so just keep focus on it
             self.focus_window(monocle);
-        } else {
+        } else if focused {
             // Focus on this window
             self.focus_window(window);
+            self.remember_focus(window);
         }
         // Give window a border
         self.border_unfocused(window);
         self.set_border_width(window, self.conf.unfocused_border.size);
+        // Pick up any urgency or fullscreen state the window was mapped with
+        self.check_urgency(window);
+        if state.contains(&self.atom("_NET_WM_STATE_FULLSCREEN")) {
+            self.set_fullscreen(window, true);
+        }
     }
 
     fn destroy_event(&mut self, destroy_notify: XDestroyEvent) {
         // Handle window destroy event
         let window = destroy_notify.window();
-        if self.is_monocle(window) {
+        if self.struts.remove(&window).is_some() {
+            self.arrange_visible();
+        }
+        if let Some((root, _)) = self.unmanaged.remove(&window) {
+            self.restack(root);
+        }
+        let Some(idx) = self.workspace_of(window) else {
+            return;
+        };
+        if self.workspaces[idx].get_monocle() == Some(window) {
             // Is monocle, clear monocle
-            self.monocle_clear();
+            self.workspaces[idx].clear_monocle();
+            self.workspaces[idx].previous_geometry = None;
+        }
+        // Remove from workspace and stop tracking the client, focus goes back to the window
+        // used before it
+        self.workspaces[idx].remove(window);
+        self.history.retain(|&w| w != window);
+        if let Some(client) = self.clients.remove(&window) {
+            if let Some(sync) = client.sync {
+                xcb::sync::destroy_alarm(&self.conn, sync.alarm);
+            }
+            self.refresh_urgency();
+        }
+        self.arrange(idx);
+        // Refocus, if the window was on the focused workspace
+        if idx != self.current_workspace() {
+            return;
         }
-        // Remove from workspace
-        self.workspace_mut().remove(window);
-        // Refocus
         if let Some(monocle) = self.workspace().get_monocle() {
             self.focus_window(monocle);
         } else {
@@ -183,293 +480,2776 @@ impl StarMan {
     }
 
     fn enter_event(&mut self, enter_notify: XEnterEvent) {
-        // Handle window enter event
+        // Handle window enter event, unless windows are being cycled through from the keyboard
+        // or only take focus when clicked
+        if self.cycle.is_some() || self.conf.focus_model == FocusModel::Click {
+            return;
+        }
         let window = enter_notify.event();
-        // Focus window
-        xcb::configure_window(
-            &self.conn,
-            window,
-            &[(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE)],
-        );
-
-        self.border_focused(window);
-        if !self.is_monocle(window) {
-            self.focus_window(window);
-            self.workspace_mut().set_focus(window);
+        if self.pointer_focus(window) && self.conf.raise == Raise::OnFocus {
+            self.raise_window(window);
         }
     }
 
     fn leave_event(&mut self, leave_notify: XLeaveEvent) {
-        // Handle window leave event
+        // Handle window leave event, focus only goes with the pointer when it follows the mouse,
+        // and not while windows are being cycled through from the keyboard
+        if self.cycle.is_some()
+            || self.conf.focus_model != FocusModel::FollowsMouse
+            || leave_notify.mode() != xcb::NOTIFY_MODE_NORMAL as u8
+            || leave_notify.detail() == xcb::NOTIFY_DETAIL_INFERIOR as u8
+        {
+            return;
+        }
         let window = leave_notify.event();
+        if self.get_input_focus() == window {
+            self.focus_window(self.root());
+        }
         self.border_unfocused(window);
     }
 
-    fn border_unfocused(&mut self, window: u32) {
-        // Change the border of a window to an unfocused border style
-        xcb::change_window_attributes(
-            &self.conn,
-            window,
-            &[(xcb::CW_BORDER_PIXEL, self.conf.unfocused_border.colour)],
-        );
+    fn click_event(&mut self, window: u32) {
+        // Handle a click on a window, focusing and raising it if the policy says to
+        let focused = self.conf.focus_model == FocusModel::Click && self.pointer_focus(window);
+        let raise = match self.conf.raise {
+            Raise::OnFocus => focused,
+            Raise::OnClick => true,
+            Raise::Never => false,
+        };
+        if raise {
+            self.raise_window(window);
+        }
     }
 
-    fn border_focused(&mut self, window: u32) {
-        // Change the border of a window to a focused border style
-        xcb::change_window_attributes(
-            &self.conn,
-            window,
-            &[(xcb::CW_BORDER_PIXEL, self.conf.focused_border.colour)],
-        );
+    fn pointer_focus(&mut self, window: u32) -> bool {
+        // Focus a window the pointer has reached, returning whether it was a client
+        let Some(monitor) = self.window_monitor(window) else {
+            return false;
+        };
+        // Focus follows the mouse onto the monitor this window is on
+        self.monitor = monitor;
+        self.publish_layout();
+        let previous = self.get_input_focus();
+        if previous != window && self.clients.contains_key(&previous) {
+            self.border_unfocused(previous);
+        }
+        self.clear_urgency(window);
+        self.border_focused(window);
+        if !self.is_monocle(window) {
+            self.focus_window(window);
+            self.workspace_mut().set_focus(window);
+            self.remember_focus(window);
+            self.draw_strip(self.current_workspace());
+        }
+        true
     }
 
-    fn button_press_event(&mut self, button_press: XButtonPressEvent) {
-        // Handle mouse button click event
-        if !self.is_monocle(button_press.child()) {
-            // Window isn't in monocle mode
-            let geo = xcb::get_geometry(&self.conn, button_press.child())
-                .get_reply()
-                .ok();
-            self.mouse = Some(MouseInfo::new(button_press, geo));
+    fn property_event(&mut self, property_notify: XPropertyEvent) {
+        // Handle window property change event
+        let window = property_notify.window();
+        if !self.clients.contains_key(&window) {
+            return;
+        }
+        // Urgency hint may have been set or cleared
+        let atom = property_notify.atom();
+        if atom == xcb::ATOM_WM_HINTS {
+            self.check_urgency(window);
+        }
+        // Title may have changed, which shows up on its tab
+        if atom == xcb::ATOM_WM_NAME || atom == self.atom("_NET_WM_NAME") {
+            if let Some(idx) = self.workspace_of(window) {
+                self.draw_strip(idx);
+            }
         }
     }
 
-    fn motion_event(&mut self, motion_event: XMotionEvent) {
-        // Handle mouse motion event
-        let resize = motion_event.state() == 321;
-        if let Some(start) = self.mouse.as_ref() {
-            let end = MouseInfo::motion(motion_event);
-            // Calculate deltas
-            let delta_x = i64::from(end.root_x - start.root_x);
-            let delta_y = i64::from(end.root_y - start.root_y);
-            if (delta_x == 0 && delta_y == 0) || start.detail != 1 {
-                // Exit if only a click, or not using the left mouse button
-                return;
-            }
-            // Move window if drag was performed
-            if let Some(geo) = start.geo {
-                if resize {
-                    let w = i64::from(geo.2) + delta_x;
-                    let h = i64::from(geo.3) + delta_y;
-                    if w > 0 && h > 0 {
-                        self.resize_window(start.child, w, h);
-                    }
-                } else {
-                    let x = geo.0 as i64 + delta_x;
-                    let y = geo.1 as i64 + delta_y;
-                    self.move_window(start.child, x, y);
-                }
-            }
+    fn expose_event(&mut self, expose: XExposeEvent) {
+        // Redraw a tab strip once the last part of it has been uncovered
+        if expose.count() != 0 {
+            return;
+        }
+        let window = expose.window();
+        let overlay = self.cycle.as_ref().and_then(|c| c.overlay.as_ref());
+        if overlay.is_some_and(|o| o.window == window) {
+            self.draw_overlay();
+            return;
+        }
+        if let Some(&idx) = self
+            .strips
+            .iter()
+            .find(|(_, s)| s.window == window)
+            .map(|(idx, _)| idx)
+        {
+            self.draw_strip(idx);
         }
     }
 
-    fn key_event(&mut self, key_press: XKeyEvent) {
-        // Handle key press events
-        let code = st!(self.keymap[&key_press.detail()][0]);
-        let modifiers = key_press.state();
-        // Create key
-        let key = Key::new(modifiers.into(), &code);
-        // Check if user defined handler
-        if let Some(handler) = self.conf.key(&key) {
-            handler(self);
+    fn client_message_event(&mut self, client_message: XClientMessageEvent) {
+        // Handle messages sent from clients to the window manager
+        let window = client_message.window();
+        let data = client_message.data().data32();
+        // Startup notifications arrive in pieces, from the launcher's own window
+        let kind = client_message.type_();
+        let begin = kind == self.atom("_NET_STARTUP_INFO_BEGIN");
+        if begin || kind == self.atom("_NET_STARTUP_INFO") {
+            self.startup_event(window, begin, client_message.data().data8());
             return;
         }
-        // Check for workspace trigger
-        if let Some(idx) = self.workspaces.iter().position(|w| w.trigger == key) {
-            // Exit if already focused
-            if idx == self.workspace {
-                return;
-            }
-            // Hide previous workspace windows
-            self.workspace().hide(&self.conn);
-            // Update index
-            self.workspace = idx;
-            // Show new workspace windows
-            self.workspace().show(&self.conn);
-            // Refocus monocle if need be
-            if let Some(monocle) = self.workspace().get_monocle() {
-                self.focus_window(monocle);
+        // Replies to pings are sent back to the root window
+        if client_message.type_() == self.atom("WM_PROTOCOLS")
+            && data[0] == self.atom("_NET_WM_PING")
+        {
+            self.pong(data[2]);
+            return;
+        }
+        if !self.clients.contains_key(&window) {
+            return;
+        }
+        if client_message.type_() == self.atom("_NET_WM_STATE") {
+            // Client wants to change up to two of its states
+            for &property in &data[1..=2] {
+                if property != xcb::NONE {
+                    self.state_event(window, data[0], property);
+                }
             }
+        } else if client_message.type_() == self.atom("_NET_WM_FULLSCREEN_MONITORS") {
+            // Client wants to be fullscreen across a set of monitors
+            self.fullscreen_monitors_event(window, [data[0], data[1], data[2], data[3]]);
         }
     }
 
-    pub fn bind<K: Into<Key>>(&mut self, key: K, handler: Handler) {
-        // Bind a key to a handler
-        let key = key.into();
-        let setup = self.conn.get_setup();
-        let screen = setup.roots().next().unwrap();
-        // Establish a grab on this shortcut
-        StarMan::grab_key(&self.conn, &screen, &key, &self.keymap);
-        // Perform the bind
-        self.conf.bind_handler(key, handler);
+    fn fullscreen_monitors_event(&mut self, window: u32, edges: [u32; 4]) {
+        // Remember which monitors a window spans when fullscreen, keeping the property up to date
+        let property = self.atom("_NET_WM_FULLSCREEN_MONITORS");
+        self.set_property32(window, property, xcb::ATOM_CARDINAL, &edges);
+        let Some(client) = self.clients.get_mut(&window) else {
+            return;
+        };
+        client.fullscreen_monitors = Some(edges);
+        // Already fullscreen, so stretch it over the new set of monitors
+        if client.fullscreen.is_some() {
+            let area = self.fullscreen_area(window, self.monitor_of(window));
+            self.reshape_window(window, area.x, area.y, area.w.into(), area.h.into());
+        }
     }
 
-    pub fn destroy(&mut self, target: u32) {
-        // Set up a destroy event
-        let protocols = xcb::intern_atom(&self.conn, false, "WM_PROTOCOLS")
-            .get_reply()
-            .unwrap()
-            .atom();
-        let delete = xcb::intern_atom(&self.conn, false, "WM_DELETE_WINDOW")
-            .get_reply()
-            .unwrap()
-            .atom();
-        let data = xcb::ClientMessageData::from_data32([delete, xcb::CURRENT_TIME, 0, 0, 0]);
-        let event = xcb::ClientMessageEvent::new(32, target, protocols, data);
-        // Clear monocle if target is monocle
-        if self.is_monocle(target) {
-            self.monocle_clear();
+    fn fullscreen_area(&self, window: u32, monitor: usize) -> Rect {
+        // Get the area a fullscreen window covers, either its monitor, or the bounding box of
+        // the monitors it asked for with _NET_WM_FULLSCREEN_MONITORS
+        let fallback = self.monitors[monitor].geometry;
+        let Some(edges) = self
+            .clients
+            .get(&window)
+            .and_then(|c| c.fullscreen_monitors)
+        else {
+            return fallback;
+        };
+        // Monitors are numbered the way Xinerama does, which is the order they're kept in
+        let root = self.monitors[monitor].root;
+        let screen: Vec<Rect> = self
+            .monitors
+            .iter()
+            .filter(|m| m.root == root)
+            .map(|m| m.geometry)
+            .collect();
+        let edge = |idx: u32| screen.get(usize::try_from(idx).ok()?);
+        let (Some(top), Some(bottom), Some(left), Some(right)) = (
+            edge(edges[0]),
+            edge(edges[1]),
+            edge(edges[2]),
+            edge(edges[3]),
+        ) else {
+            return fallback;
+        };
+        let (x, y) = (left.x, top.y);
+        let (w, h) = (right.right() - x, bottom.bottom() - y);
+        if w <= 0 || h <= 0 {
+            return fallback;
         }
-        // Send the event
-        xcb::send_event(&self.conn, false, target, xcb::EVENT_MASK_NO_EVENT, &event);
+        Rect::new(x, y, w as u32, h as u32)
     }
 
-    pub fn destroy_focus(&mut self) {
-        // Check that focus isn't monocle
-        if self.workspace().get_monocle().is_some() {
-            self.monocle_clear();
+    fn state_event(&mut self, window: u32, action: u32, property: u32) {
+        // Add, remove or toggle a _NET_WM_STATE property on a window
+        let net_wm_state = self.atom("_NET_WM_STATE");
+        let mut state = self.get_property32(window, net_wm_state, xcb::ATOM_ATOM);
+        let enable = match action {
+            NET_WM_STATE_REMOVE => false,
+            NET_WM_STATE_ADD => true,
+            NET_WM_STATE_TOGGLE => !state.contains(&property),
+            _ => return,
+        };
+        state.retain(|&s| s != property);
+        if enable {
+            state.push(property);
+            // A window can't be kept both above and below the others
+            let (above, below) = (
+                self.atom("_NET_WM_STATE_ABOVE"),
+                self.atom("_NET_WM_STATE_BELOW"),
+            );
+            if property == above {
+                state.retain(|&s| s != below);
+            } else if property == below {
+                state.retain(|&s| s != above);
+            }
         }
-        // Destroy the window that is currently focused on
-        if let Some(target) = self.workspace().get_focus() {
-            self.destroy(target);
+        self.set_property32(window, net_wm_state, xcb::ATOM_ATOM, &state);
+        // React to the new state
+        if property == self.atom("_NET_WM_STATE_DEMANDS_ATTENTION") {
+            self.check_urgency(window);
+        } else if property == self.atom("_NET_WM_STATE_FULLSCREEN") {
+            self.set_fullscreen(window, enable);
+        } else if property == self.atom("_NET_WM_STATE_ABOVE") {
+            self.set_kept(window, Layer::Above, enable);
+        } else if property == self.atom("_NET_WM_STATE_BELOW") {
+            self.set_kept(window, Layer::Below, enable);
         }
     }
 
-    pub fn move_window_to_workspace(&mut self, workspace: usize) {
-        // Move a window to a specific workspace
-        if workspace == self.workspace {
+    fn set_kept(&mut self, window: u32, layer: Layer, enable: bool) {
+        // Keep a window above or below the others in its workspace, or let it go back
+        let Some(client) = self.clients.get_mut(&window) else {
             return;
+        };
+        if enable {
+            client.kept = Some(layer);
+        } else if client.kept == Some(layer) {
+            client.kept = None;
         }
-        // Get the focused window
-        if let Some(focus) = self.workspace().get_focus() {
-            // Remove from current workspace
-            self.workspace_mut().remove(focus);
-            // Unmap the window
-            xcb::unmap_window(&self.conn, focus);
-            // Add into new workspace and set focus
-            self.workspaces[workspace].add(focus);
-            self.workspaces[workspace].set_focus(focus);
+        if let Some(idx) = self.workspace_of(window) {
+            self.restack(self.workspaces[idx].root);
         }
     }
 
-    pub fn monocle_focus(&mut self) {
-        // Set the monocle to the focused window
-        if let Some(monocle) = self.workspace_mut().set_monocle() {
-            // Get current window geometry
-            let geo = xcb::get_geometry(&self.conn, monocle).get_reply().unwrap();
-            self.workspace_mut().previous_geometry = Some((
-                i64::from(geo.x()),
-                i64::from(geo.y()),
-                u32::from(geo.width()),
-                u32::from(geo.height()),
-            ));
-            // Get window and border size
-            let window = self.conn.get_setup().roots().next().unwrap();
-            let (w, h) = (window.width_in_pixels(), window.height_in_pixels());
-            let border = (self.conf.focused_border.size * 2) as i64;
-            // Move and Resize
-            self.reshape_window(monocle, 0, 0, w as i64 - border, h as i64 - border);
+    fn set_fullscreen(&mut self, window: u32, enable: bool) {
+        // Make a window cover the whole of its monitor, or put it back where it was
+        let Some(client) = self.clients.get(&window) else {
+            return;
+        };
+        if enable && client.fullscreen.is_none() {
+            let Some(geo) = self.get_geometry(window) else {
+                return;
+            };
+            let area = self.fullscreen_area(window, self.monitor_of(window));
+            self.clients.get_mut(&window).unwrap().fullscreen = Some(geo);
+            self.set_border_width(window, 0);
+            self.reshape_window(window, area.x, area.y, area.w.into(), area.h.into());
+        } else if !enable {
+            if let Some(geo) = self.clients.get_mut(&window).unwrap().fullscreen.take() {
+                self.set_border_width(window, self.conf.unfocused_border.size);
+                self.reshape_window(window, geo.x, geo.y, geo.w.into(), geo.h.into());
+            }
+        }
+        // The rest of the workspace fills in around it, or makes room for it again
+        if let Some(idx) = self.workspace_of(window) {
+            self.arrange(idx);
         }
     }
 
-    pub fn monocle_clear(&mut self) {
-        // Clear the monocle
-        if let Some(monocle) = self.workspace_mut().clear_monocle() {
-            let geo = std::mem::take(&mut self.workspace_mut().previous_geometry).unwrap();
-            self.reshape_window(monocle, geo.0, geo.1, geo.2 as i64, geo.3 as i64);
+    fn add_struts(&mut self, window: u32, root: u32) {
+        // Remember the space a dock reserves at the edges of the screen
+        let partial = self.atom("_NET_WM_STRUT_PARTIAL");
+        let mut strut = self.get_property32(window, partial, xcb::ATOM_CARDINAL);
+        if strut.len() < 4 {
+            strut = self.get_property32(window, self.atom("_NET_WM_STRUT"), xcb::ATOM_CARDINAL);
         }
+        if strut.len() < 4 {
+            return;
+        }
+        // Without start and end values, each strut runs the whole length of its edge
+        let mut reserved = [
+            0,
+            0,
+            0,
+            0,
+            0,
+            u32::MAX,
+            0,
+            u32::MAX,
+            0,
+            u32::MAX,
+            0,
+            u32::MAX,
+        ];
+        let given = strut.len().min(12);
+        reserved[..given].copy_from_slice(&strut[..given]);
+        self.struts.insert(window, (root, reserved));
     }
-    
-    pub fn is_monocle(&mut self, window: u32) -> bool {
-        // Returns true if the window provided is in monocle mode
-        self.workspace().get_monocle() == Some(window)
+
+    fn work_area(&self, monitor: usize) -> Rect {
+        // Get the area of a monitor that isn't reserved by docks
+        let Monitor {
+            root,
+            geometry: geo,
+            ..
+        } = self.monitors[monitor];
+        let screen = self.get_geometry(root).unwrap_or(geo);
+        let (mut left, mut top, mut right, mut bottom) = (0, 0, 0, 0);
+        // Struts only reach monitors alongside the part of the edge they cover
+        let spans = |start: u32, end: u32, from: i64, to: i64| {
+            i64::from(start) < to && i64::from(end) >= from
+        };
+        for (_, strut) in self.struts.values().filter(|(r, _)| *r == root) {
+            // Struts are measured from the edges of the whole screen
+            if spans(strut[4], strut[5], geo.y, geo.bottom()) {
+                left = left.max(i64::from(strut[0]) - geo.x);
+            }
+            if spans(strut[6], strut[7], geo.y, geo.bottom()) {
+                right = right.max(geo.right() - (screen.right() - i64::from(strut[1])));
+            }
+            if spans(strut[8], strut[9], geo.x, geo.right()) {
+                top = top.max(i64::from(strut[2]) - geo.y);
+            }
+            if spans(strut[10], strut[11], geo.x, geo.right()) {
+                bottom = bottom.max(geo.bottom() - (screen.bottom() - i64::from(strut[3])));
+            }
+        }
+        let w = (i64::from(geo.w) - left - right).max(1);
+        let h = (i64::from(geo.h) - top - bottom).max(1);
+        Rect::new(geo.x + left, geo.y + top, w as u32, h as u32)
     }
 
-    fn move_window(&self, window: u32, x: i64, y: i64) {
-        // Move a window to a specific X and Y coordinate
-        xcb::configure_window(
-            &self.conn,
-            window,
-            &[
-                (xcb::CONFIG_WINDOW_X as u16, x as u32),
-                (xcb::CONFIG_WINDOW_Y as u16, y as u32),
-            ],
-        );
+    fn monitor_at(&self, root: u32, x: i64, y: i64) -> Option<usize> {
+        // Find the monitor on a screen that contains a point
+        self.monitors
+            .iter()
+            .position(|m| m.root == root && m.geometry.contains(x, y))
     }
 
-    fn resize_window(&self, window: u32, w: i64, h: i64) {
-        // Resize a window to a specific W and H size
-        xcb::configure_window(
-            &self.conn,
-            window,
-            &[
-                (xcb::CONFIG_WINDOW_WIDTH as u16, w as u32),
-                (xcb::CONFIG_WINDOW_HEIGHT as u16, h as u32),
-            ],
-        );
+    fn pointer_monitor(&self) -> usize {
+        // Find the monitor the mouse pointer is on, or the primary one
+        xcb::query_pointer(&self.conn, self.root())
+            .get_reply()
+            .ok()
+            .and_then(|p| self.monitor_at(p.root(), i64::from(p.root_x()), i64::from(p.root_y())))
+            .unwrap_or_else(|| self.primary_monitor())
     }
 
-    fn reshape_window(&self, window: u32, x: i64, y: i64, w: i64, h: i64) {
-        // Reshape a window to a specific position and size all in one
-        xcb::configure_window(
-            &self.conn,
-            window,
-            &[
-                (xcb::CONFIG_WINDOW_X as u16, x as u32),
-                (xcb::CONFIG_WINDOW_Y as u16, y as u32),
-                (xcb::CONFIG_WINDOW_WIDTH as u16, w as u32),
-                (xcb::CONFIG_WINDOW_HEIGHT as u16, h as u32),
-            ],
-        );
+    fn is_screen_change(&self, kind: u8) -> bool {
+        // Check if an event is a RandR notification about the outputs changing
+        self.randr_base.is_some_and(|base| {
+            kind == base + xcb::randr::SCREEN_CHANGE_NOTIFY || kind == base + xcb::randr::NOTIFY
+        })
     }
 
-    fn set_border_width(&self, window: u32, width: u32) {
-        // Set the border width of a window
-        xcb::configure_window(
-            &self.conn,
-            window,
-            &[(xcb::CONFIG_WINDOW_BORDER_WIDTH as u16, width)],
-        );
+    fn screen_change_event(&mut self) {
+        // Rebuild the monitor list after outputs are plugged in, unplugged or moved
+        let focused = self.current_workspace();
+        let mut monitors = vec![];
+        let mut shown = vec![];
+        for &root in &self.roots {
+            let size = self.get_geometry(root).unwrap_or_default();
+            let mut found = get_monitors(&self.conn, root, size);
+            let owned: Vec<usize> = (0..self.workspaces.len())
+                .filter(|&w| self.workspaces[w].root == root)
+                .collect();
+            found.truncate(owned.len());
+            let old: Vec<&Monitor> = self.monitors.iter().filter(|m| m.root == root).collect();
+            // Outputs that are still around keep the workspace they were showing
+            let kept: Vec<Option<usize>> = found
+                .iter()
+                .map(|new| {
+                    old.iter()
+                        .find(|old| old.name == new.name)
+                        .map(|old| old.workspace)
+                })
+                .collect();
+            // Workspaces from removed outputs go to new outputs first, then hidden workspaces
+            let displaced = old
+                .iter()
+                .map(|m| m.workspace)
+                .filter(|w| !kept.contains(&Some(*w)));
+            let hidden = owned
+                .iter()
+                .copied()
+                .filter(|&w| self.monitor_showing(w).is_none());
+            let mut spare = displaced.chain(hidden);
+            let mut slots: Vec<usize> = kept
+                .iter()
+                .map(|w| w.or_else(|| spare.next()).unwrap_or(owned[0]))
+                .collect();
+            // Keep the focused workspace in view, on the primary output if its own went away
+            if self.workspaces[focused].root == root && !slots.contains(&focused) {
+                let primary = found.iter().position(|m| m.primary).unwrap_or(0);
+                slots[primary] = focused;
+            }
+            monitors.extend(found);
+            shown.extend(slots);
+        }
+        // Hide workspaces that no longer have an output to show them
+        for monitor in &self.monitors {
+            if !shown.contains(&monitor.workspace) {
+                self.hide_workspace(monitor.workspace);
+            }
+        }
+        // Move the rest onto their outputs, pulling windows back into view, with the focused
+        // monitor pointing into the new list before anything looks it up
+        self.monitors = monitors;
+        self.monitor = shown.iter().position(|&w| w == focused).unwrap_or(0);
+        for (monitor, workspace) in shown.into_iter().enumerate() {
+            self.show_workspace(workspace, monitor);
+        }
+        if let Some(window) = self.focused_window() {
+            self.focus_window(window);
+        }
     }
 
-    fn set_cursor(conn: &xcb::Connection, screen: &xcb::Screen, k: u16) {
-        // Set the cursor on the screen
-        let f = conn.generate_id();
-        xcb::open_font(conn, f, "cursor");
-        let c = conn.generate_id();
-        xcb::create_glyph_cursor(conn, c, f, f, k, k + 1, 0, 0, 0, 0xffff, 0xffff, 0xffff);
-        xcb::change_window_attributes(conn, screen.root(), &[(xcb::CW_CURSOR, c)]);
+    fn primary_monitor(&self) -> usize {
+        // Find the primary monitor on the focused screen, or the first one if there isn't one
+        let root = self.root();
+        self.monitors
+            .iter()
+            .position(|m| m.root == root && m.primary)
+            .unwrap_or(self.monitor)
     }
 
-    #[rustfmt::skip]
-    fn get_atom_property(&self, window: u32, property: &str) -> u32 {
-        // Get a property from an atom
-        let a = xcb::intern_atom(&self.conn, true, property)
-            .get_reply()
-            .unwrap()
-            .atom();
-        let prop = xproto::get_property(&self.conn, false, window, a, xproto::ATOM_ATOM, 0, 1024)
+    fn monitor_of(&self, window: u32) -> usize {
+        // Find the monitor that holds the middle of a window
+        let root = self.root_of(window);
+        self.get_geometry(window)
+            .and_then(|geo| {
+                let (x, y) = geo.centre();
+                self.monitor_at(root, x, y)
+            })
+            .unwrap_or_else(|| self.pointer_monitor())
+    }
+
+    fn root(&self) -> u32 {
+        // Get the root window of the focused screen
+        self.monitors[self.monitor].root
+    }
+
+    fn root_of(&self, window: u32) -> u32 {
+        // Get the root window of the screen a window is on
+        xcb::get_geometry(&self.conn, window)
             .get_reply()
-            .unwrap();
-        if prop.value_len() == 0 { 42 } else { prop.value()[0] }
+            .map_or_else(|_| self.root(), |geo| geo.root())
     }
 
-    fn grab_button(conn: &xcb::Connection, screen: &xcb::Screen, button: u8, mods: u16) {
-        // Tell X to grab all mouse events with specific modifiers and buttons
-        xcb::grab_button(
-            conn,
-            false,
-            screen.root(),
-            (xcb::EVENT_MASK_BUTTON_PRESS
-                | xcb::EVENT_MASK_BUTTON_RELEASE
-                | xcb::EVENT_MASK_POINTER_MOTION) as u16,
-            xcb::GRAB_MODE_ASYNC as u8,
-            xcb::GRAB_MODE_ASYNC as u8,
-            xcb::NONE,
-            xcb::NONE,
-            button,
-            mods,
-        );
+    fn root_workspace(&self, root: u32) -> usize {
+        // Get the focused workspace on a screen
+        if root == self.root() {
+            return self.current_workspace();
+        }
+        self.monitors
+            .iter()
+            .find(|m| m.root == root)
+            .map_or_else(|| self.current_workspace(), |m| m.workspace)
+    }
+
+    fn screen_workspace(&self, number: usize) -> Option<usize> {
+        // Turn a workspace number on the focused screen into its index
+        let root = self.root();
+        (0..self.workspaces.len())
+            .filter(|&w| self.workspaces[w].root == root)
+            .nth(number)
+    }
+
+    fn place_window(&self, window: u32, monitor: usize) {
+        // Make sure a window sits within the work area of a monitor
+        let Some(geo) = self.get_geometry(window) else {
+            return;
+        };
+        let placed = geo.clamp_into(&self.work_area(monitor));
+        if placed != geo {
+            self.move_window(window, placed.x, placed.y);
+        }
+    }
+
+    fn monitor_showing(&self, workspace: usize) -> Option<usize> {
+        // Find the monitor that is showing a workspace, if any
+        self.monitors.iter().position(|m| m.workspace == workspace)
+    }
+
+    fn workspace_of(&self, window: u32) -> Option<usize> {
+        // Find the workspace that holds a window
+        self.workspaces.iter().position(|w| w.contains(window))
+    }
+
+    fn window_monitor(&self, window: u32) -> Option<usize> {
+        // Find the monitor showing the workspace that holds a window
+        self.monitor_showing(self.workspace_of(window)?)
+    }
+
+    fn arrange(&mut self, idx: usize) {
+        // Let the layout of a workspace decide where its windows go, if it's being shown
+        let Some(monitor) = self.monitor_showing(idx) else {
+            return;
+        };
+        let screen = self.work_area(monitor);
+        let windows = self.tiled_windows(idx);
+        // Leave the outer gap around the edge, and the inner gap between windows
+        let alone = windows.len() == 1;
+        let gaps = self.effective_gaps(idx, alone);
+        let border = self.border_size(alone);
+        let area = screen.inset(gaps.outer, gaps.outer, gaps.outer, gaps.outer);
+        let (before, after) = (gaps.inner / 2, gaps.inner - gaps.inner / 2);
+        let workspace = &mut self.workspaces[idx];
+        for (window, geo) in workspace.layout.arrange(&windows, area, workspace.masters) {
+            // Windows placed out of view are hidden, so they don't spill onto other monitors
+            if !geo.overlaps(&screen) {
+                xcb::unmap_window(&self.conn, window);
+                continue;
+            }
+            xcb::map_window(&self.conn, window);
+            let geo = geo.inset(
+                if geo.x > area.x { before } else { 0 },
+                if geo.y > area.y { before } else { 0 },
+                if geo.right() < area.right() { after } else { 0 },
+                if geo.bottom() < area.bottom() {
+                    after
+                } else {
+                    0
+                },
+            );
+            self.set_border_width(window, border);
+            let w = (i64::from(geo.w) - i64::from(border * 2)).max(1);
+            let h = (i64::from(geo.h) - i64::from(border * 2)).max(1);
+            self.reshape_window(window, geo.x, geo.y, w, h);
+        }
+        // Layouts with tabs get a strip drawn above their windows, the monocle hides it
+        let workspace = &self.workspaces[idx];
+        let tabs = if workspace.get_monocle().is_some() {
+            vec![]
+        } else {
+            workspace.layout.tabs(&windows, area)
+        };
+        self.update_strip(idx, tabs);
+        self.restack(self.workspaces[idx].root);
+    }
+
+    fn raise_window(&mut self, window: u32) {
+        // Bring a window to the top of its layer
+        if let Some(idx) = self.workspace_of(window) {
+            self.workspaces[idx].stack.raise(window);
+            self.restack(self.workspaces[idx].root);
+        }
+    }
+
+    fn layer(&self, idx: usize, window: u32) -> Layer {
+        // Work out which layer a window on a workspace belongs in
+        let Some(client) = self.clients.get(&window) else {
+            return Layer::Normal;
+        };
+        if client.fullscreen.is_some() {
+            Layer::Fullscreen
+        } else if self.workspaces[idx].get_monocle() == Some(window) {
+            Layer::Above
+        } else if let Some(layer) = client.kept {
+            layer
+        } else if client.floating {
+            Layer::Floating
+        } else {
+            Layer::Normal
+        }
+    }
+
+    fn restack(&mut self, root: u32) {
+        // Stack the windows shown on a screen layer by layer, and within each layer in the
+        // order they were raised, then publish that order
+        let mut windows: Vec<(u32, Layer)> = self
+            .unmanaged
+            .iter()
+            .filter(|(_, (r, _))| *r == root)
+            .map(|(&window, &(_, layer))| (window, layer))
+            .collect();
+        let mut hidden = vec![];
+        for (idx, workspace) in self.workspaces.iter().enumerate() {
+            if workspace.root != root {
+                continue;
+            }
+            let order = workspace.stack.order(|w| self.layer(idx, w));
+            if self.monitor_showing(idx).is_none() {
+                hidden.extend(order.into_iter().map(|(window, _)| window));
+                continue;
+            }
+            windows.extend(order);
+            // Tab strips sit with the tiles they label
+            if let Some(strip) = self.strips.get(&idx) {
+                windows.push((strip.window, Layer::Normal));
+            }
+        }
+        windows.sort_by_key(|&(_, layer)| layer);
+        let order: Vec<u32> = windows.into_iter().map(|(window, _)| window).collect();
+        // Windows on hidden workspaces count as being underneath everything shown
+        let stacking: Vec<u32> = hidden
+            .into_iter()
+            .chain(order.iter().copied())
+            .filter(|w| self.clients.contains_key(w))
+            .collect();
+        let (last_order, last_stacking) = self.stacked.remove(&root).unwrap_or_default();
+        // Windows below the first one to have moved are already where they should be
+        let same = order
+            .iter()
+            .zip(&last_order)
+            .take_while(|(a, b)| a == b)
+            .count();
+        // Start from there and put each window just above the last, so anything else StarWM
+        // shows, like drag previews, stays on top
+        let mut below = same.checked_sub(1).map(|idx| order[idx]);
+        for &window in &order[same..] {
+            let mode = (xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE);
+            match below {
+                Some(sibling) => xcb::configure_window(
+                    &self.conn,
+                    window,
+                    &[(xcb::CONFIG_WINDOW_SIBLING as u16, sibling), mode],
+                ),
+                None => {
+                    xcb::configure_window(&self.conn, window, &[(mode.0, xcb::STACK_MODE_BELOW)])
+                }
+            };
+            below = Some(window);
+        }
+        if stacking != last_stacking {
+            let property = self.atom("_NET_CLIENT_LIST_STACKING");
+            self.set_property32(root, property, xcb::ATOM_WINDOW, &stacking);
+        }
+        self.stacked.insert(root, (order, stacking));
+    }
+
+    fn float_window(&mut self, window: u32) {
+        // Take a tiled window out of its layout, so it can be placed by hand
+        let Some(idx) = self.workspace_of(window) else {
+            return;
+        };
+        if !self.workspaces[idx].layout.tiles() {
+            return;
+        }
+        match self.clients.get_mut(&window) {
+            Some(client) if !client.floating => client.floating = true,
+            _ => return,
+        }
+        self.set_border_width(window, self.conf.unfocused_border.size);
+        self.arrange(idx);
+        self.raise_window(window);
+    }
+
+    fn tiled_windows(&self, idx: usize) -> Vec<u32> {
+        // Get the windows of a workspace that its layout arranges, fullscreen windows cover
+        // everything anyway, and floating windows go wherever they're put
+        self.workspaces[idx]
+            .clients()
+            .iter()
+            .copied()
+            .filter(|w| {
+                self.clients
+                    .get(w)
+                    .is_none_or(|c| c.fullscreen.is_none() && !c.floating)
+            })
+            .collect()
+    }
+
+    fn effective_gaps(&self, idx: usize, alone: bool) -> Gaps {
+        // Get the gaps of a workspace, which are dropped when turned off, or when smart gaps
+        // are on and there is only one window to show
+        let gaps = self.workspaces[idx].gaps;
+        if !gaps.enabled || (alone && self.conf.smart_gaps) {
+            Gaps {
+                inner: 0,
+                outer: 0,
+                ..gaps
+            }
+        } else {
+            gaps
+        }
+    }
+
+    fn border_size(&self, alone: bool) -> u32 {
+        // Get the border width of tiled windows, dropped for a lone window with smart borders
+        if alone && self.conf.smart_borders {
+            0
+        } else {
+            self.conf.unfocused_border.size
+        }
+    }
+
+    fn update_strip(&mut self, idx: usize, tabs: Vec<(u32, Rect)>) {
+        // Show the tab strip of a workspace, or get rid of it once there are no tabs
+        if tabs.is_empty() {
+            if let Some(strip) = self.strips.remove(&idx) {
+                strip.destroy(&self.conn);
+            }
+            return;
+        }
+        let (conn, root, font) = (&self.conn, self.workspaces[idx].root, self.conf.tab_font);
+        self.strips
+            .entry(idx)
+            .or_insert_with(|| Strip::new(conn, root, font))
+            .place(conn, tabs);
+        // Windows share the area below the strip, so bring the focused one to the front
+        if let Some(focus) = self.workspaces[idx].get_focus() {
+            self.workspaces[idx].stack.raise(focus);
+        }
+        self.draw_strip(idx);
+    }
+
+    fn draw_strip(&self, idx: usize) {
+        // Redraw the tabs of a workspace, picking out the focused window
+        let Some(strip) = self.strips.get(&idx) else {
+            return;
+        };
+        let focus = self.workspaces[idx].get_focus();
+        let labels: Vec<(String, u32)> = strip
+            .windows()
+            .map(|window| {
+                let border = self
+                    .state_border(window)
+                    .unwrap_or(if focus == Some(window) {
+                        &self.conf.focused_border
+                    } else {
+                        &self.conf.unfocused_border
+                    });
+                (self.window_title(window), border.colour)
+            })
+            .collect();
+        strip.draw(&self.conn, &labels, self.conf.tab_text);
+    }
+
+    fn hide_workspace(&self, idx: usize) {
+        // Hide the windows of a workspace, along with its tab strip
+        self.workspaces[idx].hide(&self.conn);
+        if let Some(strip) = self.strips.get(&idx) {
+            strip.hide(&self.conn);
+        }
+    }
+
+    fn arrange_visible(&mut self) {
+        // Arrange every workspace that is being shown, after the space on screen changes
+        let shown: Vec<usize> = self.monitors.iter().map(|m| m.workspace).collect();
+        for idx in shown {
+            self.arrange(idx);
+        }
+    }
+
+    fn focused_monitor(&self) -> usize {
+        // Work out which monitor has focus, from the pointer when it's over the bare root
+        // window, or else from the window with keyboard focus
+        let pointer = xcb::query_pointer(&self.conn, self.root()).get_reply().ok();
+        let over_root = pointer.as_ref().is_some_and(|p| p.child() == xcb::NONE);
+        if !over_root {
+            if let Some(monitor) = self.window_monitor(self.get_input_focus()) {
+                return monitor;
+            }
+        }
+        pointer
+            .and_then(|p| self.monitor_at(p.root(), i64::from(p.root_x()), i64::from(p.root_y())))
+            .unwrap_or(self.monitor)
+    }
+
+    pub fn focus_monitor(&mut self, monitor: usize) {
+        // Move focus, and the pointer, over to another monitor
+        if monitor >= self.monitors.len() {
+            return;
+        }
+        self.monitor = monitor;
+        self.publish_layout();
+        let target = if let Some(window) = self.focused_window() {
+            self.activate(window);
+            self.get_geometry(window)
+        } else {
+            self.focus_window(self.root());
+            Some(self.monitors[monitor].geometry)
+        };
+        if let Some(target) = target {
+            let (x, y) = target.centre();
+            xcb::warp_pointer(
+                &self.conn,
+                xcb::NONE,
+                self.root(),
+                0,
+                0,
+                0,
+                0,
+                x as i16,
+                y as i16,
+            );
+        }
+    }
+
+    fn show_workspace(&mut self, idx: usize, monitor: usize) {
+        // Show a workspace on a monitor, bringing its windows over from where they were
+        let area = self.monitors[monitor].geometry;
+        self.monitors[monitor].workspace = idx;
+        if let Some(previous) = self.workspaces[idx].area.replace(area) {
+            let (dx, dy) = (area.x - previous.x, area.y - previous.y);
+            let windows: Vec<u32> = self.workspaces[idx].windows().collect();
+            for window in windows {
+                self.translate_window(window, dx, dy);
+            }
+            if let Some(geo) = self.workspaces[idx].previous_geometry.as_mut() {
+                geo.0 += dx;
+                geo.1 += dy;
+            }
+        }
+        self.workspaces[idx].show(&self.conn);
+        self.refit_workspace(idx, monitor);
+        self.publish_layout();
+    }
+
+    fn translate_window(&mut self, window: u32, dx: i64, dy: i64) {
+        // Shift a window by an offset, along with any geometry saved for it
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        if let Some(client) = self.clients.get_mut(&window) {
+            for geo in client
+                .fullscreen
+                .iter_mut()
+                .chain(client.float_geometry.iter_mut())
+            {
+                geo.x += dx;
+                geo.y += dy;
+            }
+        }
+        if let Some(geo) = self.get_geometry(window) {
+            self.move_window(window, geo.x + dx, geo.y + dy);
+        }
+    }
+
+    fn refit_workspace(&mut self, idx: usize, monitor: usize) {
+        // Fit the windows of a workspace to the monitor it is shown on
+        let monocle = self.workspaces[idx].get_monocle();
+        let windows: Vec<u32> = self.workspaces[idx].windows().collect();
+        for window in windows {
+            if self
+                .clients
+                .get(&window)
+                .is_some_and(|c| c.fullscreen.is_some())
+            {
+                let area = self.fullscreen_area(window, monitor);
+                self.reshape_window(window, area.x, area.y, area.w.into(), area.h.into());
+            } else if monocle == Some(window) {
+                self.fit_monocle(window, monitor);
+            } else {
+                self.place_window(window, monitor);
+            }
+        }
+        self.arrange(idx);
+    }
+
+    fn adopt_window(&mut self, window: u32, from: Rect, workspace: usize) {
+        // Shift a window coming from an area into the area its new workspace was last shown
+        if let Some(area) = self.workspaces[workspace].area {
+            self.translate_window(window, area.x - from.x, area.y - from.y);
+        }
+    }
+
+    fn current_workspace(&self) -> usize {
+        // Get the index of the workspace on the focused monitor
+        self.monitors[self.monitor].workspace
+    }
+
+    fn check_urgency(&mut self, window: u32) {
+        // Work out if a window is urgent from its WM_HINTS and _NET_WM_STATE
+        let hints = self.get_property32(window, xcb::ATOM_WM_HINTS, xcb::ATOM_WM_HINTS);
+        let hinted = hints.first().is_some_and(|flags| flags & URGENCY_HINT != 0);
+        let attention = self.atom("_NET_WM_STATE_DEMANDS_ATTENTION");
+        let state = self.get_property32(window, self.atom("_NET_WM_STATE"), xcb::ATOM_ATOM);
+        let urgent = hinted || state.contains(&attention);
+        // Don't bother marking the window the user is already looking at
+        if urgent && self.get_input_focus() == window {
+            self.clear_urgency(window);
+            return;
+        }
+        if let Some(client) = self.clients.get_mut(&window) {
+            if client.set_urgent(urgent) {
+                self.border_unfocused(window);
+                self.refresh_urgency();
+            }
+        }
+    }
+
+    fn clear_urgency(&mut self, window: u32) {
+        // Remove urgency from a window, along with the hints that caused it
+        let mut hints = self.get_property32(window, xcb::ATOM_WM_HINTS, xcb::ATOM_WM_HINTS);
+        if let Some(flags) = hints.first_mut() {
+            if *flags & URGENCY_HINT != 0 {
+                *flags &= !URGENCY_HINT;
+                self.set_property32(window, xcb::ATOM_WM_HINTS, xcb::ATOM_WM_HINTS, &hints);
+            }
+        }
+        let net_wm_state = self.atom("_NET_WM_STATE");
+        let attention = self.atom("_NET_WM_STATE_DEMANDS_ATTENTION");
+        let mut state = self.get_property32(window, net_wm_state, xcb::ATOM_ATOM);
+        if state.contains(&attention) {
+            state.retain(|&s| s != attention);
+            self.set_property32(window, net_wm_state, xcb::ATOM_ATOM, &state);
+        }
+        if let Some(client) = self.clients.get_mut(&window) {
+            if client.set_urgent(false) {
+                self.refresh_urgency();
+            }
+        }
+    }
+
+    fn startup_event(&mut self, window: u32, begin: bool, data: &[u8]) {
+        // Collect the pieces of a startup notification message, and act on it once whole
+        let buffer = self.startup_messages.entry(window).or_default();
+        if begin {
+            buffer.clear();
+        }
+        let end = data.iter().position(|&b| b == 0);
+        buffer.extend_from_slice(&data[..end.unwrap_or(data.len())]);
+        if end.is_none() {
+            return;
+        }
+        let message = self.startup_messages.remove(&window).unwrap_or_default();
+        match startup::parse(&String::from_utf8_lossy(&message)) {
+            // Another launcher started a program, so place it on the current workspace
+            Some(("new", id)) if !self.launches.iter().any(|l| l.id == id) => {
+                self.launches
+                    .push(Launch::new(id, self.current_workspace()));
+            }
+            // Program finished starting
+            Some(("remove", id)) => self.launches.retain(|l| l.id != id),
+            _ => (),
+        }
+        self.update_cursor();
+    }
+
+    fn take_launch(&mut self, window: u32) -> Option<usize> {
+        // Find the workspace that a newly mapped window was launched from
+        let property = self.atom("_NET_STARTUP_ID");
+        let id = self.get_string_property(window, property, self.atom("UTF8_STRING"));
+        let idx = self.launches.iter().position(|l| l.id == id)?;
+        let launch = self.launches.remove(idx);
+        self.update_cursor();
+        Some(launch.workspace)
+    }
+
+    fn expire_launches(&mut self) {
+        // Forget about programs that never finished starting up
+        if self.launches.iter().any(Launch::expired) {
+            self.launches.retain(|l| !l.expired());
+            self.update_cursor();
+        }
+    }
+
+    fn update_cursor(&self) {
+        // Show a busy cursor while programs are starting up
+        let cursor = if self.launches.is_empty() {
+            CURSOR_NORMAL
+        } else {
+            CURSOR_BUSY
+        };
+        for screen in self.conn.get_setup().roots() {
+            StarMan::set_cursor(&self.conn, &screen, cursor);
+        }
+    }
+
+    fn check_pings(&mut self) {
+        // Ping clients that are due one, and mark those that never answered as hung
+        let ping = self.atom("_NET_WM_PING");
+        let mut due = vec![];
+        let mut expired = vec![];
+        for (&window, client) in &self.clients {
+            if !client.supports(ping) {
+                continue;
+            }
+            if client.ping_due() {
+                due.push(window);
+            } else if client.ping_expired() && !client.hung {
+                expired.push(window);
+            }
+        }
+        for window in due {
+            self.ping(window);
+        }
+        for window in expired {
+            if let Some(client) = self.clients.get_mut(&window) {
+                client.hung = true;
+            }
+            self.border_refresh(window);
+        }
+    }
+
+    fn ping(&mut self, window: u32) {
+        // Send a _NET_WM_PING to a client, unless one is already on its way
+        if let Some(client) = self.clients.get_mut(&window) {
+            if client.ping.is_some() {
+                return;
+            }
+            client.ping = Some(Instant::now());
+        }
+        self.send_protocol(window, "_NET_WM_PING", [window, 0, 0]);
+    }
+
+    fn pong(&mut self, window: u32) {
+        // Handle a client answering a ping
+        if let Some(client) = self.clients.get_mut(&window) {
+            client.ping = None;
+            client.pong = Instant::now();
+            client.kill_offered = false;
+            if std::mem::take(&mut client.hung) {
+                self.border_refresh(window);
+            }
+        }
+    }
+
+    fn refresh_urgency(&mut self) {
+        // Aggregate the urgency of clients into their workspaces
+        let clients = &self.clients;
+        let mut changed = vec![];
+        for workspace in &mut self.workspaces {
+            let urgent = workspace
+                .windows()
+                .any(|w| clients.get(&w).is_some_and(Client::is_urgent));
+            if workspace.urgent != urgent && !changed.contains(&workspace.root) {
+                changed.push(workspace.root);
+            }
+            workspace.urgent = urgent;
+        }
+        // Let bars know which workspaces on each screen are urgent, by their number there
+        let property = self.atom("_STARWM_URGENT");
+        for root in changed {
+            let urgent: Vec<u32> = self
+                .workspaces
+                .iter()
+                .filter(|w| w.root == root)
+                .enumerate()
+                .filter(|(_, w)| w.urgent)
+                .filter_map(|(number, _)| u32::try_from(number).ok())
+                .collect();
+            self.set_property32(root, property, xcb::ATOM_CARDINAL, &urgent);
+        }
+    }
+
+    fn border_unfocused(&mut self, window: u32) {
+        // Change the border of a window to an unfocused border style
+        let border = self
+            .state_border(window)
+            .unwrap_or(&self.conf.unfocused_border);
+        xcb::change_window_attributes(&self.conn, window, &[(xcb::CW_BORDER_PIXEL, border.colour)]);
+        self.apply_opacity(window, border.opacity);
+    }
+
+    fn border_focused(&mut self, window: u32) {
+        // Change the border of a window to a focused border style
+        let border = self
+            .state_border(window)
+            .unwrap_or(&self.conf.focused_border);
+        xcb::change_window_attributes(&self.conn, window, &[(xcb::CW_BORDER_PIXEL, border.colour)]);
+        self.apply_opacity(window, border.opacity);
+    }
+
+    fn apply_opacity(&self, window: u32, state: f64) {
+        // Set the opacity of a window from its focus state, its rule and any manual change
+        let Some(client) = self.clients.get(&window) else {
+            return;
+        };
+        let opacity = (state * self.client_opacity(client)).clamp(0.0, 1.0);
+        let property = self.atom("_NET_WM_WINDOW_OPACITY");
+        if opacity >= 1.0 {
+            // Fully opaque windows don't need the property at all
+            xcb::delete_property(&self.conn, window, property);
+        } else {
+            let value = (opacity * f64::from(u32::MAX)) as u32;
+            self.set_property32(window, property, xcb::ATOM_CARDINAL, &[value]);
+        }
+    }
+
+    fn client_opacity(&self, client: &Client) -> f64 {
+        // Get the opacity of a client, before its focus state is taken into account
+        client
+            .opacity
+            .or_else(|| self.conf.rule(&client.class)?.opacity)
+            .unwrap_or(1.0)
+    }
+
+    fn border_refresh(&mut self, window: u32) {
+        // Redraw the border of a window after its state has changed
+        if self.get_input_focus() == window {
+            self.border_focused(window);
+        } else {
+            self.border_unfocused(window);
+        }
+    }
+
+    fn state_border(&self, window: u32) -> Option<&WindowBorder> {
+        // Get the border style that overrides focus, if the client is hung or urgent
+        let client = self.clients.get(&window)?;
+        if client.hung {
+            Some(&self.conf.hung_border)
+        } else if client.is_urgent() {
+            Some(&self.conf.urgent_border)
+        } else {
+            None
+        }
+    }
+
+    fn button_press_event(&mut self, button_press: XButtonPressEvent) {
+        // Handle mouse button click event, a click on a tab brings its window to the front
+        let (x, y) = (
+            i64::from(button_press.root_x()),
+            i64::from(button_press.root_y()),
+        );
+        if let Some((&idx, strip)) = self
+            .strips
+            .iter()
+            .find(|(_, s)| s.window == button_press.event())
+        {
+            let tab = strip.tab_at(x, y);
+            if let (Some(window), Some(monitor)) = (tab, self.monitor_showing(idx)) {
+                self.monitor = monitor;
+                self.publish_layout();
+                self.activate(window);
+            }
+            return;
+        }
+        // Clicks caught on a window itself are passed on once it's been focused
+        if self.clients.contains_key(&button_press.event()) {
+            self.click_event(button_press.event());
+            xcb::allow_events(
+                &self.conn,
+                xcb::ALLOW_REPLAY_POINTER as u8,
+                button_press.time(),
+            );
+            return;
+        }
+        if !self.is_monocle(button_press.child()) {
+            // Window isn't in monocle mode
+            let geo = xcb::get_geometry(&self.conn, button_press.child())
+                .get_reply()
+                .ok();
+            let mut mouse = MouseInfo::new(button_press, geo);
+            // Resizing with [Shift] held, or with the resize button, is always free, otherwise
+            // tiles are dragged around the layout
+            let shift = u32::from(button_press.state()) & xcb::MOD_MASK_SHIFT != 0;
+            if shift || mouse.detail == self.conf.resize_button {
+                mouse.drag = self.start_resize(mouse.child, x, y, button_press.time());
+            } else {
+                mouse.drag = self.start_drag(mouse.child, x, y);
+            }
+            self.mouse = Some(mouse);
+        }
+    }
+
+    fn start_resize(&self, window: u32, x: i64, y: i64, time: u32) -> Drag {
+        // Work out which edges a resize moves, showing a cursor to match
+        let Some(geo) = self.get_geometry(window) else {
+            return Drag::Free;
+        };
+        let hints = self.get_property32(window, xcb::ATOM_WM_NORMAL_HINTS, xcb::ATOM_WM_SIZE_HINTS);
+        let drag = Drag::resize(geo, x, y, SizeHints::new(&hints));
+        let Drag::Resize {
+            horizontal,
+            vertical,
+            ..
+        } = drag
+        else {
+            return drag;
+        };
+        let cursor = match (horizontal, vertical) {
+            (Some(Direction::Left), Some(Direction::Up)) => CURSOR_TOP_LEFT,
+            (Some(Direction::Left), Some(_)) => CURSOR_BOTTOM_LEFT,
+            (Some(_), Some(Direction::Up)) => CURSOR_TOP_RIGHT,
+            (Some(_), Some(_)) => CURSOR_BOTTOM_RIGHT,
+            (Some(Direction::Left), None) => CURSOR_LEFT,
+            (Some(_), None) => CURSOR_RIGHT,
+            (None, Some(Direction::Up)) => CURSOR_TOP,
+            (None, _) => CURSOR_BOTTOM,
+        };
+        // The cursor goes back to normal on its own once the button is let go
+        let c = StarMan::create_cursor(&self.conn, cursor);
+        xcb::change_active_pointer_grab(&self.conn, c, time, DRAG_EVENTS as u16);
+        xcb::free_cursor(&self.conn, c);
+        drag
+    }
+
+    fn start_drag(&self, window: u32, x: i64, y: i64) -> Drag {
+        // Work out what dragging a window does, only tiled windows are dragged around the layout
+        let Some(idx) = self.workspace_of(window) else {
+            return Drag::Free;
+        };
+        let tiled = self.tiled_windows(idx);
+        if !self.workspaces[idx].layout.tiles() || !tiled.contains(&window) {
+            return Drag::Free;
+        }
+        let Some(geo) = self.get_geometry(window) else {
+            return Drag::Free;
+        };
+        // Starting near an edge shared with another tile moves the boundary between them, as
+        // long as the layout has a boundary there to move
+        let sides = [
+            (Direction::Left, x - geo.x),
+            (Direction::Right, geo.right() - x),
+            (Direction::Up, y - geo.y),
+            (Direction::Down, geo.bottom() - y),
+        ];
+        let (edge, distance) = sides.iter().copied().min_by_key(|(_, d)| *d).unwrap();
+        let shared = tiled
+            .iter()
+            .filter(|&&w| w != window)
+            .filter_map(|&w| self.get_geometry(w))
+            .any(|other| geo.distance_towards(&other, edge).is_some());
+        let workspace = &self.workspaces[idx];
+        let master = workspace
+            .find(window)
+            .is_some_and(|i| i < workspace.masters.count);
+        if distance <= EDGE_MARGIN && shared && workspace.layout.resizes(master, edge) {
+            return Drag::Split { edge, last: (x, y) };
+        }
+        // Otherwise the window is picked up, to be dropped somewhere else
+        Drag::Tile {
+            preview: self.create_preview(self.workspaces[idx].root),
+            drop: None,
+        }
+    }
+
+    fn drag_split(&mut self, window: u32, edge: Direction, moved: (i64, i64)) {
+        // Move the boundary on an edge of a tiled window along with the pointer
+        let Some(idx) = self.workspace_of(window) else {
+            return;
+        };
+        let Some(monitor) = self.monitor_showing(idx) else {
+            return;
+        };
+        let area = self.work_area(monitor);
+        let (distance, length) = match edge {
+            Direction::Left | Direction::Right => (moved.0, area.w),
+            Direction::Up | Direction::Down => (moved.1, area.h),
+        };
+        // Pulling the right or bottom edge outwards makes the window bigger, and vice versa
+        let mut grow = distance as f64 / f64::from(length.max(1));
+        if matches!(edge, Direction::Left | Direction::Up) {
+            grow = -grow;
+        }
+        // Layouts that can't resize one window have their master area resized instead
+        let workspace = &mut self.workspaces[idx];
+        if workspace.layout.handle(Message::Resize(grow), window) {
+            self.arrange(idx);
+            return;
+        }
+        let master = workspace
+            .find(window)
+            .is_some_and(|i| i < workspace.masters.count);
+        self.change_master_ratio(idx, if master { grow } else { -grow });
+    }
+
+    fn find_drop(&self, window: u32, x: i64, y: i64) -> (Option<Drop>, Option<Rect>) {
+        // Work out where a tiled window would go if dropped at a point, and what area that
+        // would take up
+        let Some(monitor) = self.monitor_at(self.root_of(window), x, y) else {
+            return (None, None);
+        };
+        let idx = self.monitors[monitor].workspace;
+        for other in self.tiled_windows(idx) {
+            let Some(geo) = self.get_geometry(other) else {
+                continue;
+            };
+            if other == window || !geo.contains(x, y) {
+                continue;
+            }
+            // Near an edge of the tile, the window goes beside it, otherwise they swap
+            let (w, h) = (f64::from(geo.w.max(1)), f64::from(geo.h.max(1)));
+            let sides = [
+                (Direction::Left, (x - geo.x) as f64 / w),
+                (Direction::Right, (geo.right() - x) as f64 / w),
+                (Direction::Up, (y - geo.y) as f64 / h),
+                (Direction::Down, (geo.bottom() - y) as f64 / h),
+            ];
+            let (edge, share) = sides
+                .iter()
+                .copied()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            if share >= DROP_EDGE {
+                return (Some(Drop::Swap(other)), Some(geo));
+            }
+            let half = match edge {
+                Direction::Left => geo.split_x(geo.w / 2).0,
+                Direction::Right => geo.split_x(geo.w / 2).1,
+                Direction::Up => geo.split_y(geo.h / 2).0,
+                Direction::Down => geo.split_y(geo.h / 2).1,
+            };
+            return (Some(Drop::Beside(other, edge)), Some(half));
+        }
+        // Over another monitor, but not a tile, so it goes onto that monitor's workspace
+        if self.window_monitor(window) != Some(monitor) {
+            return (Some(Drop::Monitor(monitor)), Some(self.work_area(monitor)));
+        }
+        (None, None)
+    }
+
+    fn drop_tile(&mut self, window: u32, drop: Option<Drop>, moved: (i64, i64)) {
+        // Put a dragged tile down where it was dropped
+        let Some(monitor) = self.window_monitor(window) else {
+            return;
+        };
+        self.monitor = monitor;
+        self.activate(window);
+        let Some(drop) = drop else {
+            // Dropped away from any tile, so it floats where it was left
+            self.float_window(window);
+            if let Some(geo) = self.get_geometry(window) {
+                self.move_window(window, geo.x + moved.0, geo.y + moved.1);
+            }
+            return;
+        };
+        // Dropping onto another monitor takes the window over to its workspace first
+        let target = match drop {
+            Drop::Swap(target) | Drop::Beside(target, _) => self.window_monitor(target),
+            Drop::Monitor(target) => Some(target),
+        };
+        if let Some(target) = target.filter(|&t| t != monitor) {
+            self.follow_window_to_monitor(target);
+        }
+        let Some(idx) = self.workspace_of(window) else {
+            return;
+        };
+        let workspace = &mut self.workspaces[idx];
+        match drop {
+            Drop::Swap(other) => {
+                workspace.swap(window, other);
+                workspace.layout.swap(window, other);
+            }
+            Drop::Beside(other, edge) => {
+                let after = matches!(edge, Direction::Right | Direction::Down);
+                workspace.place_beside(window, other, after);
+                workspace.layout.place_beside(window, other, edge);
+            }
+            Drop::Monitor(_) => (),
+        }
+        self.arrange(idx);
+        self.activate(window);
+    }
+
+    fn create_preview(&self, root: u32) -> u32 {
+        // Create the see-through window that shows where a dragged tile will land
+        let window = self.conn.generate_id();
+        xcb::create_window(
+            &self.conn,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            xcb::WINDOW_CLASS_INPUT_OUTPUT as u16,
+            xcb::COPY_FROM_PARENT,
+            &[
+                (xcb::CW_BACK_PIXEL, self.conf.focused_border.colour),
+                (xcb::CW_OVERRIDE_REDIRECT, 1),
+            ],
+        );
+        let opacity = (PREVIEW_OPACITY * f64::from(u32::MAX)) as u32;
+        let property = self.atom("_NET_WM_WINDOW_OPACITY");
+        self.set_property32(window, property, xcb::ATOM_CARDINAL, &[opacity]);
+        window
+    }
+
+    fn show_preview(&self, preview: u32, area: Option<Rect>) {
+        // Cover the area a dragged tile would take up, or hide the preview if there isn't one
+        let Some(area) = area else {
+            xcb::unmap_window(&self.conn, preview);
+            return;
+        };
+        xcb::configure_window(
+            &self.conn,
+            preview,
+            &[
+                (xcb::CONFIG_WINDOW_X as u16, area.x as u32),
+                (xcb::CONFIG_WINDOW_Y as u16, area.y as u32),
+                (xcb::CONFIG_WINDOW_WIDTH as u16, area.w),
+                (xcb::CONFIG_WINDOW_HEIGHT as u16, area.h),
+                (xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE),
+            ],
+        );
+        xcb::map_window(&self.conn, preview);
+    }
+
+    fn motion_event(&mut self, motion_event: XMotionEvent) {
+        // Handle mouse motion event
+        if let Some(start) = self.mouse.as_ref() {
+            let end = MouseInfo::motion(motion_event);
+            // Calculate deltas
+            let delta_x = i64::from(end.root_x - start.root_x);
+            let delta_y = i64::from(end.root_y - start.root_y);
+            let resize = matches!(start.drag, Drag::Resize { .. });
+            if (delta_x == 0 && delta_y == 0) || (start.detail != 1 && !resize) {
+                // Exit if only a click, or not using the left mouse button or resizing
+                return;
+            }
+            let (child, geo) = (start.child, start.geo);
+            let (x, y) = (i64::from(end.root_x), i64::from(end.root_y));
+            match start.drag {
+                Drag::Split { edge, last } => {
+                    self.drag_split(child, edge, (x - last.0, y - last.1));
+                    if let Some(MouseInfo {
+                        drag: Drag::Split { last, .. },
+                        ..
+                    }) = self.mouse.as_mut()
+                    {
+                        *last = (x, y);
+                    }
+                    return;
+                }
+                Drag::Tile { preview, .. } => {
+                    let (drop, area) = self.find_drop(child, x, y);
+                    self.show_preview(preview, area);
+                    if let Some(MouseInfo {
+                        drag: Drag::Tile { drop: old, .. },
+                        ..
+                    }) = self.mouse.as_mut()
+                    {
+                        *old = drop;
+                    }
+                    return;
+                }
+                Drag::Resize {
+                    horizontal,
+                    vertical,
+                    hints,
+                } => {
+                    self.float_window(child);
+                    if let Some(geo) = geo {
+                        let geo = Rect::new(geo.0, geo.1, geo.2, geo.3);
+                        let geo =
+                            resize_edges(geo, horizontal, vertical, (delta_x, delta_y), hints);
+                        self.sync_reshape(child, geo);
+                    }
+                    return;
+                }
+                Drag::Free => (),
+            }
+            // Dragging a tiled window freely takes it out of the layout, as dwm does
+            self.float_window(child);
+            // Move window if drag was performed
+            if let Some(geo) = geo {
+                let x = geo.0 as i64 + delta_x;
+                let y = geo.1 as i64 + delta_y;
+                self.move_window(child, x, y);
+            }
+        }
+    }
+
+    fn button_release_event(&mut self, button_release: XButtonReleaseEvent) {
+        // Handle mouse button release event, finishing off any held back resize, or dropping
+        // a dragged tile
+        let mouse = self.mouse.take();
+        self.flush_sync_resizes(true);
+        if let Some(MouseInfo {
+            root_x,
+            root_y,
+            child,
+            drag: Drag::Tile { preview, drop },
+            ..
+        }) = mouse
+        {
+            xcb::destroy_window(&self.conn, preview);
+            let moved = (
+                i64::from(button_release.root_x() - root_x),
+                i64::from(button_release.root_y() - root_y),
+            );
+            self.drop_tile(child, drop, moved);
+        }
+    }
+
+    fn get_sync_counter(&self, window: u32, client: &Client) -> Option<SyncCounter> {
+        // Find the _NET_WM_SYNC_REQUEST counter of a client that supports it
+        if !client.supports(self.atom("_NET_WM_SYNC_REQUEST")) {
+            return None;
+        }
+        let property = self.atom("_NET_WM_SYNC_REQUEST_COUNTER");
+        let counter = *self
+            .get_property32(window, property, xcb::ATOM_CARDINAL)
+            .first()?;
+        let value = self.get_sync_value(counter)?;
+        let alarm = self.create_alarm(counter, value + 1);
+        Some(SyncCounter::new(alarm, value))
+    }
+
+    fn create_alarm(&self, counter: u32, value: i64) -> u32 {
+        // Ask to be told once a sync counter reaches a value, rather than checking on it
+        let alarm = self.conn.generate_id();
+        let mask = xcb::sync::CA_COUNTER
+            | xcb::sync::CA_VALUE_TYPE
+            | xcb::sync::CA_VALUE
+            | xcb::sync::CA_TEST_TYPE
+            | xcb::sync::CA_DELTA
+            | xcb::sync::CA_EVENTS;
+        // Values go over the wire packed in the order of the mask, 64 bit ones high half first
+        let values: [u32; 8] = [
+            counter,
+            xcb::sync::VALUETYPE_ABSOLUTE,
+            (value >> 32) as u32,
+            value as u32,
+            xcb::sync::TESTTYPE_POSITIVE_COMPARISON,
+            0,
+            0,
+            1,
+        ];
+        unsafe {
+            xcb::ffi::sync::xcb_sync_create_alarm(
+                self.conn.get_raw_conn(),
+                alarm,
+                mask,
+                values.as_ptr().cast(),
+            );
+        }
+        alarm
+    }
+
+    fn set_alarm(&self, alarm: u32, value: i64) {
+        // Move an alarm on to a new value, which sets it going again
+        let values: [u32; 2] = [(value >> 32) as u32, value as u32];
+        unsafe {
+            xcb::ffi::sync::xcb_sync_change_alarm(
+                self.conn.get_raw_conn(),
+                alarm,
+                xcb::sync::CA_VALUE,
+                values.as_ptr().cast(),
+            );
+        }
+    }
+
+    fn alarm_event(&mut self, alarm_notify: XAlarmEvent) {
+        // Handle a sync alarm going off, a client has redrawn, so give it any size held back
+        let alarm = alarm_notify.alarm();
+        let Some((&window, sync)) = self
+            .clients
+            .iter_mut()
+            .find_map(|(w, c)| Some((w, c.sync.as_mut().filter(|s| s.alarm == alarm)?)))
+        else {
+            return;
+        };
+        sync.sent = None;
+        if let Some(geo) = sync.deferred {
+            self.sync_reshape(window, geo);
+        }
+    }
+
+    fn get_sync_value(&self, counter: u32) -> Option<i64> {
+        // Read the current value of a sync counter
+        let value = xcb::sync::query_counter(&self.conn, counter)
+            .get_reply()
+            .ok()?
+            .counter_value();
+        Some(i64::from(value.hi()) << 32 | i64::from(value.lo()))
+    }
+
+    fn sync_reshape(&mut self, window: u32, geo: Rect) {
+        // Reshape a window, in step with its redraws if it supports _NET_WM_SYNC_REQUEST
+        let ready = match self.clients.get(&window).and_then(|c| c.sync.as_ref()) {
+            None => {
+                self.reshape_window(window, geo.x, geo.y, geo.w.into(), geo.h.into());
+                return;
+            }
+            Some(sync) => sync.timed_out(),
+        };
+        let sync = self
+            .clients
+            .get_mut(&window)
+            .unwrap()
+            .sync
+            .as_mut()
+            .unwrap();
+        if !ready {
+            // Client is still drawing the last size, hold onto this one for later
+            sync.deferred = Some(geo);
+            return;
+        }
+        // Tell the client which counter value to set once it has redrawn
+        sync.value += 1;
+        sync.sent = Some(Instant::now());
+        sync.deferred = None;
+        let (value, alarm) = (sync.value, sync.alarm);
+        self.set_alarm(alarm, value);
+        self.send_protocol(
+            window,
+            "_NET_WM_SYNC_REQUEST",
+            [value as u32, (value >> 32) as u32, 0],
+        );
+        self.reshape_window(window, geo.x, geo.y, geo.w.into(), geo.h.into());
+    }
+
+    fn flush_sync_resizes(&mut self, force: bool) {
+        // Apply resizes that were held back while waiting for clients to redraw
+        let deferred: Vec<(u32, Rect)> = self
+            .clients
+            .iter()
+            .filter_map(|(&window, c)| Some((window, c.sync.as_ref()?.deferred?)))
+            .collect();
+        for (window, geo) in deferred {
+            if force {
+                if let Some(sync) = self.clients.get_mut(&window).and_then(|c| c.sync.as_mut()) {
+                    sync.sent = None;
+                }
+            }
+            self.sync_reshape(window, geo);
+        }
+    }
+
+    fn key_event(&mut self, key_press: XKeyEvent) {
+        // Handle key press events, on whichever monitor has focus
+        self.monitor = self.focused_monitor();
+        self.publish_layout();
+        // Keys with nothing mapped to them can't be bound, so there's nothing to do
+        let Some(code) = self.keysym(key_press.detail()) else {
+            return;
+        };
+        let modifiers = key_press.state();
+        // Escape backs out of cycling through windows
+        if self.cycle.is_some() && code == "Escape" {
+            self.finish_cycle(false);
+            return;
+        }
+        // Create key
+        let key = Key::new(modifiers.into(), &code);
+        // Check if user defined handler
+        if let Some(handler) = self.conf.key(&key) {
+            handler(self);
+            return;
+        }
+        // Check for workspace trigger on the focused screen
+        let root = self.root();
+        if let Some(idx) = self
+            .workspaces
+            .iter()
+            .position(|w| w.root == root && w.trigger == key)
+        {
+            self.switch_workspace(idx);
+        }
+    }
+
+    fn keysym(&self, keycode: u8) -> Option<String> {
+        // Get the name of the first symbol on a key, if it has any
+        self.keymap
+            .get(&keycode)
+            .and_then(|syms| syms.first())
+            .map(|sym| st!(sym))
+    }
+
+    fn key_release_event(&mut self, key_release: XKeyEvent) {
+        // Letting go of the held modifier settles on the window cycled to
+        if self.cycle.is_none() {
+            return;
+        }
+        if self
+            .keysym(key_release.detail())
+            .is_some_and(|code| is_hold_key(&code))
+        {
+            self.finish_cycle(true);
+        }
+    }
+
+    pub fn switch_workspace(&mut self, idx: usize) {
+        // Workspaces can only be shown on their own screen, so go over there first
+        let root = self.workspaces[idx].root;
+        if root != self.root() {
+            if let Some(monitor) = self.monitors.iter().position(|m| m.root == root) {
+                self.focus_monitor(monitor);
+            }
+        }
+        // Exit if already focused
+        let current = self.current_workspace();
+        if idx == current {
+            return;
+        }
+        match self.monitor_showing(idx) {
+            // Shown elsewhere, so focus that monitor instead
+            Some(other) if self.conf.workspace_switch == WorkspaceSwitch::Focus => {
+                self.focus_monitor(other);
+                return;
+            }
+            // Shown elsewhere, so swap workspaces with that monitor
+            Some(other) => {
+                self.show_workspace(current, other);
+                self.show_workspace(idx, self.monitor);
+            }
+            // Hidden, so hide previous workspace windows and show the new ones
+            None => {
+                self.hide_workspace(current);
+                self.show_workspace(idx, self.monitor);
+            }
+        }
+        // Refocus monocle if need be
+        if let Some(monocle) = self.workspace().get_monocle() {
+            self.focus_window(monocle);
+        }
+    }
+
+    pub fn bind<K: Into<Key>>(&mut self, key: K, handler: Handler) {
+        // Bind a key to a handler
+        let key = key.into();
+        // Establish a grab on this shortcut, on every screen
+        for screen in self.conn.get_setup().roots() {
+            StarMan::grab_key(&self.conn, &screen, &key, &self.keymap);
+        }
+        // Perform the bind
+        self.conf.bind_handler(key, handler);
+    }
+
+    pub fn destroy(&mut self, target: u32) {
+        // Clear monocle if target is monocle
+        if self.is_monocle(target) {
+            self.monocle_clear();
+        }
+        // A hung client may never answer, so closing it again while it's still hung forces it
+        // to close instead
+        if let Some(client) = self.clients.get_mut(&target).filter(|c| c.hung) {
+            if client.kill_offered {
+                self.force_kill(target);
+                return;
+            }
+            client.kill_offered = true;
+        }
+        // Ask the window to close, and check that it's still responding
+        self.send_protocol(target, "WM_DELETE_WINDOW", [0, 0, 0]);
+        if self
+            .clients
+            .get(&target)
+            .is_some_and(|c| c.supports(self.atom("_NET_WM_PING")))
+        {
+            self.ping(target);
+        }
+    }
+
+    pub fn force_kill(&mut self, target: u32) {
+        // Kill the process behind a window if it's local, otherwise cut its X connection
+        let pid = self
+            .get_property32(target, self.atom("_NET_WM_PID"), xcb::ATOM_CARDINAL)
+            .first()
+            .and_then(|&pid| libc::pid_t::try_from(pid).ok())
+            .filter(|&pid| pid > 0);
+        let machine =
+            self.get_string_property(target, xcb::ATOM_WM_CLIENT_MACHINE, xcb::ATOM_STRING);
+        match pid {
+            Some(pid) if machine == hostname() => unsafe {
+                libc::kill(pid, libc::SIGKILL);
+            },
+            _ => {
+                xcb::kill_client(&self.conn, target);
+            }
+        }
+    }
+
+    pub fn rule(&mut self, class: &str, rule: Rule) {
+        // Add a rule for windows with a specific WM_CLASS
+        self.conf.add_rule(class, rule);
+    }
+
+    pub fn resize_button(&mut self, button: u8) {
+        // Choose the button that resizes windows when dragged with [Meta] held, the left
+        // button always resizes with [Shift] held too
+        for screen in self.conn.get_setup().roots() {
+            if self.conf.resize_button != 1 {
+                xcb::ungrab_button(
+                    &self.conn,
+                    self.conf.resize_button,
+                    screen.root(),
+                    META as u16,
+                );
+            }
+            StarMan::grab_button(&self.conn, &screen, button, META as u16);
+        }
+        self.conf.resize_button = button;
+    }
+
+    pub fn workspace_switch(&mut self, mode: WorkspaceSwitch) {
+        // Choose what happens when switching to a workspace shown on another monitor
+        self.conf.workspace_switch = mode;
+    }
+
+    pub fn focus_model(&mut self, model: FocusModel) {
+        // Choose how the pointer moves focus between windows
+        self.conf.focus_model = model;
+        self.regrab_clicks();
+    }
+
+    pub fn raise(&mut self, policy: Raise) {
+        // Choose when a window focused with the pointer is brought to the top
+        self.conf.raise = policy;
+        self.regrab_clicks();
+    }
+
+    pub fn masters(&mut self, masters: Masters) {
+        // Set how many master windows every workspace has, and how much of it they get
+        let masters = Masters {
+            ratio: masters.ratio.clamp(MASTER_MIN, MASTER_MAX),
+            ..masters
+        };
+        self.conf.masters = masters;
+        for workspace in &mut self.workspaces {
+            workspace.masters = masters;
+        }
+        self.arrange_visible();
+    }
+
+    pub fn gaps(&mut self, gaps: Gaps) {
+        // Set the gaps used on every workspace
+        self.conf.gaps = gaps;
+        for workspace in &mut self.workspaces {
+            workspace.gaps = gaps;
+        }
+        self.arrange_visible();
+    }
+
+    pub fn workspace_gaps(&mut self, number: usize, gaps: Gaps) {
+        // Override the gaps of one workspace number, on every screen
+        for (idx, workspace) in self.workspaces.iter_mut().enumerate() {
+            if idx % WORKSPACE_KEYS.len() == number {
+                workspace.gaps = gaps;
+            }
+        }
+        self.arrange_visible();
+    }
+
+    pub fn smart_gaps(&mut self, enable: bool) {
+        // Choose whether a window on its own goes without gaps
+        self.conf.smart_gaps = enable;
+        self.arrange_visible();
+    }
+
+    pub fn smart_borders(&mut self, enable: bool) {
+        // Choose whether a window on its own goes without a border
+        self.conf.smart_borders = enable;
+        self.arrange_visible();
+    }
+
+    pub fn opacity_increase(&mut self) {
+        // Make the focused window more opaque
+        self.change_opacity(OPACITY_STEP);
+    }
+
+    pub fn opacity_decrease(&mut self) {
+        // Make the focused window more transparent
+        self.change_opacity(-OPACITY_STEP);
+    }
+
+    pub fn opacity_reset(&mut self) {
+        // Return the focused window to the opacity given by its rule
+        if let Some(window) = self.focused_window() {
+            if let Some(client) = self.clients.get_mut(&window) {
+                client.opacity = None;
+            }
+            self.border_refresh(window);
+        }
+    }
+
+    fn change_opacity(&mut self, step: f64) {
+        // Change the opacity of the focused window by a step
+        if let Some(window) = self.focused_window() {
+            if let Some(client) = self.clients.get(&window) {
+                let opacity = (self.client_opacity(client) + step).clamp(OPACITY_STEP, 1.0);
+                self.clients.get_mut(&window).unwrap().opacity = Some(opacity);
+            }
+            self.border_refresh(window);
+        }
+    }
+
+    pub fn focused_window(&self) -> Option<u32> {
+        // Get the window that is focused on the current workspace, monocle first
+        self.workspace()
+            .get_monocle()
+            .or_else(|| self.workspace().get_focus())
+    }
+
+    pub fn destroy_focus(&mut self) {
+        // Check that focus isn't monocle
+        if self.workspace().get_monocle().is_some() {
+            self.monocle_clear();
+        }
+        // Destroy the window that is currently focused on
+        if let Some(target) = self.workspace().get_focus() {
+            self.destroy(target);
+        }
+    }
+
+    pub fn spawn(&mut self, command: &str) {
+        // Run a command, placing its windows on this workspace once they appear
+        self.launched += 1;
+        let id = format!(
+            "starwm/{}-{}-{}",
+            hostname(),
+            std::process::id(),
+            self.launched
+        );
+        self.launches
+            .push(Launch::new(id.clone(), self.current_workspace()));
+        self.update_cursor();
+        let command = command.to_string();
+        cmd!(command, id);
+    }
+
+    pub fn focus_urgent(&mut self) {
+        // Jump to the window that most recently demanded attention
+        let target = self
+            .clients
+            .iter()
+            .filter_map(|(&window, client)| Some((window, client.urgent?)))
+            .max_by_key(|&(_, since)| since)
+            .map(|(window, _)| window);
+        if let Some(window) = target {
+            self.jump_to(window);
+        }
+    }
+
+    fn jump_to(&mut self, window: u32) {
+        // Switch to the workspace holding a window, and focus it there
+        if let Some(idx) = self.workspace_of(window) {
+            self.switch_workspace(idx);
+            self.activate(window);
+        }
+    }
+
+    fn remember_focus(&mut self, window: u32) {
+        // Move a window to the front of the focus history across every workspace
+        self.history.retain(|&w| w != window);
+        self.history.insert(0, window);
+    }
+
+    pub fn cycle_windows(&mut self) {
+        // Step through the windows on this workspace, from the most recently used
+        self.cycle(false, true);
+    }
+
+    pub fn cycle_windows_back(&mut self) {
+        // Step backwards through the windows on this workspace
+        self.cycle(false, false);
+    }
+
+    pub fn cycle_all_windows(&mut self) {
+        // Step through the windows on every workspace, from the most recently used
+        self.cycle(true, true);
+    }
+
+    pub fn cycle_all_windows_back(&mut self) {
+        // Step backwards through the windows on every workspace
+        self.cycle(true, false);
+    }
+
+    fn cycle(&mut self, global: bool, forward: bool) {
+        // Move on through recently used windows, taking hold of the keyboard to find out when
+        // the modifier is let go
+        if self.cycle.is_none() {
+            let windows = if global {
+                self.history.clone()
+            } else {
+                self.workspace().history().to_vec()
+            };
+            if windows.len() < 2 {
+                return;
+            }
+            let grab = xcb::grab_keyboard(
+                &self.conn,
+                false,
+                self.root(),
+                xcb::CURRENT_TIME,
+                xcb::GRAB_MODE_ASYNC as u8,
+                xcb::GRAB_MODE_ASYNC as u8,
+            )
+            .get_reply();
+            if grab.map_or(true, |g| g.status() != xcb::GRAB_STATUS_SUCCESS as u8) {
+                return;
+            }
+            let overlay = self
+                .conf
+                .cycle_overlay
+                .then(|| Strip::new(&self.conn, self.root(), self.conf.tab_font));
+            self.cycle = Some(Cycle::new(windows, overlay));
+        }
+        let window = self.cycle.as_mut().unwrap().step(forward);
+        self.jump_to(window);
+        self.draw_overlay();
+        // The modifier may have been let go before the keyboard was grabbed, so its release
+        // would never be seen
+        let mask = xcb::query_pointer(&self.conn, self.root())
+            .get_reply()
+            .map_or(0, |reply| u32::from(reply.mask()));
+        if mask & HOLD_MASK == 0 {
+            self.finish_cycle(true);
+        }
+    }
+
+    fn finish_cycle(&mut self, commit: bool) {
+        // Let go of the keyboard, and settle on the window cycled to, or go back to the start
+        let Some(cycle) = self.cycle.take() else {
+            return;
+        };
+        xcb::ungrab_keyboard(&self.conn, xcb::CURRENT_TIME);
+        if let Some(overlay) = &cycle.overlay {
+            overlay.destroy(&self.conn);
+        }
+        let window = if commit {
+            cycle.current()
+        } else {
+            cycle.original()
+        };
+        self.jump_to(window);
+    }
+
+    fn draw_overlay(&mut self) {
+        // List the windows being cycled through in the middle of the focused monitor, picking
+        // out the one cycled to
+        let Some(cycle) = &self.cycle else {
+            return;
+        };
+        let count = cycle.windows.len();
+        let height = TAB_HEIGHT * u32::try_from(count).unwrap_or(u32::MAX);
+        let (x, y) = self.monitors[self.monitor].geometry.centre();
+        let area = Rect::new(
+            x - i64::from(OVERLAY_WIDTH / 2),
+            y - i64::from(height / 2),
+            OVERLAY_WIDTH,
+            height,
+        );
+        let tabs: Vec<(u32, Rect)> = cycle
+            .windows
+            .iter()
+            .copied()
+            .zip(area.rows(count))
+            .collect();
+        let labels: Vec<(String, u32)> = cycle
+            .windows
+            .iter()
+            .enumerate()
+            .map(|(idx, &window)| {
+                let border = if idx == cycle.idx {
+                    &self.conf.focused_border
+                } else {
+                    &self.conf.unfocused_border
+                };
+                (self.window_title(window), border.colour)
+            })
+            .collect();
+        let text = self.conf.tab_text;
+        if let Some(overlay) = self.cycle.as_mut().and_then(|c| c.overlay.as_mut()) {
+            overlay.place(&self.conn, tabs);
+            overlay.draw(&self.conn, &labels, text);
+        }
+    }
+
+    pub fn move_window_to_workspace(&mut self, number: usize) {
+        // Move a window to a specific workspace on its screen
+        let Some(workspace) = self.screen_workspace(number) else {
+            return;
+        };
+        if workspace == self.current_workspace() {
+            return;
+        }
+        // Get the focused window
+        if let Some(focus) = self.workspace().get_focus() {
+            // Remove from current workspace
+            self.workspace_mut().remove(focus);
+            // Bring it into the area of the new workspace
+            let from = self.monitors[self.monitor].geometry;
+            self.adopt_window(focus, from, workspace);
+            match self.monitor_showing(workspace) {
+                // Shown on another monitor, so keep it in view over there
+                Some(monitor) => self.place_window(focus, monitor),
+                // Hidden, so unmap the window
+                None => {
+                    xcb::unmap_window(&self.conn, focus);
+                }
+            }
+            // Add into new workspace and set focus
+            self.workspaces[workspace].add(focus);
+            self.workspaces[workspace].set_focus(focus);
+            self.refresh_urgency();
+            // Rearrange both workspaces around the change
+            self.arrange(self.current_workspace());
+            self.arrange(workspace);
+        }
+    }
+
+    fn visible_windows(&self, root: u32) -> Vec<(u32, Rect)> {
+        // Get every window that can be seen on a screen, along with where it is
+        let mut visible = vec![];
+        for monitor in self.monitors.iter().filter(|m| m.root == root) {
+            let workspace = &self.workspaces[monitor.workspace];
+            let windows = match workspace.get_monocle() {
+                Some(monocle) => vec![monocle],
+                None => workspace.clients().to_vec(),
+            };
+            for window in windows {
+                let viewable = xcb::get_window_attributes(&self.conn, window)
+                    .get_reply()
+                    .is_ok_and(|a| u32::from(a.map_state()) == xcb::MAP_STATE_VIEWABLE);
+                if let (true, Some(geo)) = (viewable, self.get_geometry(window)) {
+                    visible.push((window, geo));
+                }
+            }
+        }
+        visible
+    }
+
+    fn window_towards(&self, direction: Direction) -> Option<u32> {
+        // Find the nearest visible window in a direction from the focused one, or from the
+        // focused monitor if nothing has focus
+        let focus = self.focused_window();
+        let here = focus
+            .and_then(|w| self.get_geometry(w))
+            .unwrap_or(self.monitors[self.monitor].geometry);
+        self.visible_windows(self.root())
+            .into_iter()
+            .filter(|(w, _)| Some(*w) != focus)
+            .filter_map(|(w, geo)| Some((w, here.distance_towards(&geo, direction)?)))
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(w, _)| w)
+    }
+
+    pub fn focus_towards(&mut self, direction: Direction) {
+        // Focus the nearest window in a direction, on another monitor if need be, letting the
+        // layout have its say first since it may have windows out of view that way
+        let idx = self.current_workspace();
+        let beside = self.workspace().get_focus().and_then(|focus| {
+            self.tiled_windows(idx)
+                .contains(&focus)
+                .then(|| self.workspace().layout.towards(focus, direction))
+                .flatten()
+        });
+        let Some(window) = beside.or_else(|| self.window_towards(direction)) else {
+            // Nothing that way, but there may be an empty monitor
+            self.focus_monitor_towards(direction);
+            return;
+        };
+        if let Some(monitor) = self.window_monitor(window) {
+            self.monitor = monitor;
+            self.publish_layout();
+            self.activate(window);
+        }
+    }
+
+    pub fn swap_towards(&mut self, direction: Direction) {
+        // Swap the focused window with the nearest window in a direction
+        let Some(focus) = self.workspace().get_focus() else {
+            return;
+        };
+        // Monocle windows sit outside their workspace's windows, so they can't trade places
+        let Some(other) = self
+            .window_towards(direction)
+            .filter(|&w| self.workspaces.iter().all(|ws| ws.get_monocle() != Some(w)))
+        else {
+            return;
+        };
+        let (Some(here), Some(there)) = (self.workspace_of(focus), self.workspace_of(other)) else {
+            return;
+        };
+        // Tiles on the same workspace swap places in the layout
+        let tiled = self.tiled_windows(here);
+        if here == there && tiled.contains(&focus) && tiled.contains(&other) {
+            self.workspaces[here].swap(focus, other);
+            self.workspaces[here].layout.swap(focus, other);
+            self.arrange(here);
+            self.activate(focus);
+            return;
+        }
+        // Otherwise they trade workspaces and positions
+        if here != there {
+            self.workspaces[here].replace(focus, other);
+            self.workspaces[there].replace(other, focus);
+            self.refresh_urgency();
+        }
+        if let (Some(a), Some(b)) = (self.get_geometry(focus), self.get_geometry(other)) {
+            self.reshape_window(focus, b.x, b.y, b.w.into(), b.h.into());
+            self.reshape_window(other, a.x, a.y, a.w.into(), a.h.into());
+        }
+        self.arrange(here);
+        self.arrange(there);
+        // Keep focus on the window, over on whichever monitor it ended up
+        if let Some(monitor) = self.window_monitor(focus) {
+            self.monitor = monitor;
+            self.publish_layout();
+        }
+        self.activate(focus);
+    }
+
+    pub fn move_towards(&mut self, direction: Direction) {
+        // Move the focused window in a direction, onto the next monitor once it can't go further
+        let Some(focus) = self.workspace().get_focus() else {
+            return;
+        };
+        let idx = self.current_workspace();
+        if self.tiled_windows(idx).contains(&focus) && self.workspace().layout.tiles() {
+            // Layouts that track where windows are get to move it themselves
+            if self
+                .workspace_mut()
+                .layout
+                .handle(Message::Move(direction), focus)
+            {
+                self.arrange(idx);
+                return;
+            }
+            // Otherwise swap with the nearest tile that way
+            let here = self.get_geometry(focus).unwrap_or_default();
+            let nearest = self
+                .tiled_windows(idx)
+                .into_iter()
+                .filter(|&w| w != focus)
+                .filter_map(|w| {
+                    Some((w, here.distance_towards(&self.get_geometry(w)?, direction)?))
+                })
+                .min_by_key(|(_, distance)| *distance);
+            if let Some((other, _)) = nearest {
+                self.workspace_mut().swap(focus, other);
+                self.workspace_mut().layout.swap(focus, other);
+                self.arrange(idx);
+                self.activate(focus);
+                return;
+            }
+        } else if let Some(geo) = self.get_geometry(focus) {
+            // Nudge floating windows along, as far as the edge of the monitor
+            let (dx, dy) = match direction {
+                Direction::Left => (-MOVE_STEP, 0),
+                Direction::Right => (MOVE_STEP, 0),
+                Direction::Up => (0, -MOVE_STEP),
+                Direction::Down => (0, MOVE_STEP),
+            };
+            let moved = Rect::new(geo.x + dx, geo.y + dy, geo.w, geo.h)
+                .clamp_into(&self.work_area(self.monitor));
+            if moved != geo {
+                self.move_window(focus, moved.x, moved.y);
+                return;
+            }
+        }
+        // Already as far as it goes, so carry on over to the next monitor
+        if let Some(monitor) = self.monitor_towards(direction) {
+            self.follow_window_to_monitor(monitor);
+        }
+    }
+
+    pub fn next_monitor(&self) -> usize {
+        // Get the monitor after the focused one, wrapping around
+        (self.monitor + 1) % self.monitors.len()
+    }
+
+    pub fn prev_monitor(&self) -> usize {
+        // Get the monitor before the focused one, wrapping around
+        (self.monitor + self.monitors.len() - 1) % self.monitors.len()
+    }
+
+    pub fn monitor_towards(&self, direction: Direction) -> Option<usize> {
+        // Find the nearest monitor in a direction from the focused one
+        let here = self.monitors[self.monitor].geometry;
+        self.monitors
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, m)| Some((idx, here.distance_towards(&m.geometry, direction)?)))
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(idx, _)| idx)
+    }
+
+    pub fn focus_next_monitor(&mut self) {
+        // Focus the next monitor along
+        self.focus_monitor(self.next_monitor());
+    }
+
+    pub fn focus_prev_monitor(&mut self) {
+        // Focus the previous monitor
+        self.focus_monitor(self.prev_monitor());
+    }
+
+    pub fn focus_monitor_towards(&mut self, direction: Direction) {
+        // Focus the nearest monitor in a direction
+        if let Some(monitor) = self.monitor_towards(direction) {
+            self.focus_monitor(monitor);
+        }
+    }
+
+    pub fn move_window_to_monitor(&mut self, monitor: usize) {
+        // Move a window to the workspace shown on another monitor of the same screen
+        if monitor >= self.monitors.len()
+            || monitor == self.monitor
+            || self.monitors[monitor].root != self.root()
+        {
+            return;
+        }
+        // Get the focused window
+        if let Some(focus) = self.workspace().get_focus() {
+            // Drop out of monocle, it only makes sense on its own workspace
+            if self.is_monocle(focus) {
+                self.monocle_clear();
+            }
+            // Remove from current workspace
+            self.workspace_mut().remove(focus);
+            // Keep its position and size relative to the monitor it's on
+            let from = self.monitors[self.monitor].geometry;
+            let to = self.monitors[monitor].geometry;
+            if let Some(client) = self.clients.get_mut(&focus) {
+                if let Some(saved) = client.fullscreen.as_mut() {
+                    *saved = saved.rescale(&from, &to);
+                }
+            }
+            if self
+                .clients
+                .get(&focus)
+                .is_some_and(|c| c.fullscreen.is_some())
+            {
+                self.reshape_window(focus, to.x, to.y, to.w.into(), to.h.into());
+            } else if let Some(geo) = self.get_geometry(focus) {
+                let geo = geo.rescale(&from, &to);
+                self.reshape_window(focus, geo.x, geo.y, geo.w.into(), geo.h.into());
+                self.place_window(focus, monitor);
+            }
+            // Add into new workspace and set focus
+            let workspace = self.monitors[monitor].workspace;
+            self.workspaces[workspace].add(focus);
+            self.workspaces[workspace].set_focus(focus);
+            self.refresh_urgency();
+            // Rearrange both workspaces around the change
+            self.arrange(self.current_workspace());
+            self.arrange(workspace);
+            // Give focus to whatever is left behind
+            if let Some(target) = self.focused_window() {
+                self.focus_window(target);
+            }
+        }
+    }
+
+    pub fn follow_window_to_monitor(&mut self, monitor: usize) {
+        // Move a window to another monitor, and focus it over there
+        let Some(focus) = self.workspace().get_focus() else {
+            return;
+        };
+        self.move_window_to_monitor(monitor);
+        if self.window_monitor(focus) == Some(monitor) {
+            self.focus_monitor(monitor);
+        }
+    }
+
+    pub fn swap_workspaces_with_monitor(&mut self, monitor: usize) {
+        // Swap the workspaces shown on the focused monitor and another one on the same screen
+        if monitor >= self.monitors.len()
+            || monitor == self.monitor
+            || self.monitors[monitor].root != self.root()
+        {
+            return;
+        }
+        let here = self.current_workspace();
+        let there = self.monitors[monitor].workspace;
+        self.show_workspace(here, monitor);
+        self.show_workspace(there, self.monitor);
+        if let Some(target) = self.focused_window() {
+            self.focus_window(target);
+        }
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        // Toggle fullscreen on the focused window
+        if let Some(window) = self.focused_window() {
+            let fullscreen = self.atom("_NET_WM_STATE_FULLSCREEN");
+            self.state_event(window, NET_WM_STATE_TOGGLE, fullscreen);
+        }
+    }
+
+    pub fn monocle_focus(&mut self) {
+        // Set the monocle to the focused window
+        if let Some(monocle) = self.workspace_mut().set_monocle() {
+            // Get current window geometry
+            let geo = self.get_geometry(monocle).unwrap_or_default();
+            self.workspace_mut().previous_geometry = Some((geo.x, geo.y, geo.w, geo.h));
+            // Fill the focused monitor, above the rest of the workspace
+            self.fit_monocle(monocle, self.monitor);
+            self.restack(self.root());
+        }
+    }
+
+    fn fit_monocle(&self, monocle: u32, monitor: usize) {
+        // Make a monocle window fill the work area of a monitor, the only window to be seen
+        let gaps = self.effective_gaps(self.monitors[monitor].workspace, true);
+        let area = self
+            .work_area(monitor)
+            .inset(gaps.outer, gaps.outer, gaps.outer, gaps.outer);
+        let size = self.border_size(true);
+        self.set_border_width(monocle, size);
+        let border = i64::from(size * 2);
+        self.reshape_window(
+            monocle,
+            area.x,
+            area.y,
+            i64::from(area.w) - border,
+            i64::from(area.h) - border,
+        );
+    }
+
+    pub fn monocle_clear(&mut self) {
+        // Clear the monocle
+        if let Some(monocle) = self.workspace_mut().clear_monocle() {
+            let geo = std::mem::take(&mut self.workspace_mut().previous_geometry).unwrap();
+            self.set_border_width(monocle, self.conf.unfocused_border.size);
+            self.reshape_window(monocle, geo.0, geo.1, geo.2 as i64, geo.3 as i64);
+            self.arrange(self.current_workspace());
+        }
+    }
+
+    pub fn grow_master(&mut self) {
+        // Give the master windows more of the workspace
+        self.change_master_ratio(self.current_workspace(), MASTER_STEP);
+    }
+
+    pub fn shrink_master(&mut self) {
+        // Give the master windows less of the workspace
+        self.change_master_ratio(self.current_workspace(), -MASTER_STEP);
+    }
+
+    fn change_master_ratio(&mut self, idx: usize, delta: f64) {
+        // Change the share of a workspace that master windows get
+        let masters = &mut self.workspaces[idx].masters;
+        masters.ratio = (masters.ratio + delta).clamp(MASTER_MIN, MASTER_MAX);
+        self.arrange(idx);
+    }
+
+    pub fn add_master(&mut self) {
+        // Make room for one more master window, up to every window on the workspace
+        let idx = self.current_workspace();
+        let limit = self.tiled_windows(idx).len().max(1);
+        let masters = &mut self.workspace_mut().masters;
+        masters.count = (masters.count + 1).min(limit);
+        self.arrange(idx);
+    }
+
+    pub fn remove_master(&mut self) {
+        // Make room for one less master window
+        let masters = &mut self.workspace_mut().masters;
+        masters.count = masters.count.saturating_sub(1);
+        self.arrange(self.current_workspace());
+    }
+
+    pub fn promote_master(&mut self) {
+        // Make the focused window a master, or swap it with the next window if it already is
+        if let Some(focus) = self.workspace().get_focus() {
+            self.workspace_mut().promote(focus);
+            self.arrange(self.current_workspace());
+            if let Some(focus) = self.workspace().get_focus() {
+                self.activate(focus);
+            }
+        }
+    }
+
+    pub fn rotate_stack(&mut self) {
+        // Cycle windows through the master and stack positions
+        self.workspace_mut().rotate(true);
+        self.arrange(self.current_workspace());
+    }
+
+    pub fn rotate_stack_back(&mut self) {
+        // Cycle windows through the master and stack positions, the other way
+        self.workspace_mut().rotate(false);
+        self.arrange(self.current_workspace());
+    }
+
+    pub fn toggle_floating(&mut self) {
+        // Float the focused window where it last floated, or put it back into the layout
+        let Some(window) = self.workspace().get_focus() else {
+            return;
+        };
+        let geometry = self.get_geometry(window);
+        let Some(client) = self.clients.get_mut(&window) else {
+            return;
+        };
+        client.floating = !client.floating;
+        if client.floating {
+            let saved = client.float_geometry;
+            self.set_border_width(window, self.conf.unfocused_border.size);
+            if let Some(geo) = saved {
+                self.reshape_window(window, geo.x, geo.y, geo.w.into(), geo.h.into());
+                self.place_window(window, self.monitor);
+            }
+        } else {
+            client.float_geometry = geometry;
+        }
+        self.arrange(self.current_workspace());
+        self.raise_window(window);
+    }
+
+    pub fn grow_gaps(&mut self) {
+        // Widen the gaps on the focused workspace
+        let gaps = &mut self.workspace_mut().gaps;
+        gaps.inner += GAP_STEP;
+        gaps.outer += GAP_STEP;
+        self.arrange(self.current_workspace());
+    }
+
+    pub fn shrink_gaps(&mut self) {
+        // Narrow the gaps on the focused workspace
+        let gaps = &mut self.workspace_mut().gaps;
+        gaps.inner = gaps.inner.saturating_sub(GAP_STEP);
+        gaps.outer = gaps.outer.saturating_sub(GAP_STEP);
+        self.arrange(self.current_workspace());
+    }
+
+    pub fn toggle_gaps(&mut self) {
+        // Turn the gaps on the focused workspace off, or back on again
+        let gaps = &mut self.workspace_mut().gaps;
+        gaps.enabled = !gaps.enabled;
+        self.arrange(self.current_workspace());
+    }
+
+    pub fn set_layout<L: Layout + 'static>(&mut self, layout: L) {
+        // Change how the focused workspace arranges its windows
+        self.replace_layout(Box::new(layout));
+    }
+
+    fn replace_layout(&mut self, layout: Box<dyn Layout>) {
+        // Swap in a new layout, bringing back any windows the old one had out of view
+        self.workspace_mut().layout = layout;
+        self.workspace().show(&self.conn);
+        self.arrange(self.current_workspace());
+        self.publish_layout();
+    }
+
+    pub fn cycle_layout(&mut self) {
+        // Move the focused workspace on to the next layout in the configuration
+        let layouts = &self.conf.layouts;
+        let current = layouts.iter().position(|&name| name == self.layout_name());
+        let next = current.map_or(0, |idx| (idx + 1) % layouts.len());
+        if let Some(layout) = layouts.get(next).and_then(|name| layout::from_name(name)) {
+            self.replace_layout(layout);
+        }
+    }
+
+    pub fn send_layout(&mut self, message: Message) {
+        // Pass an action on to the layout of the focused workspace
+        let Some(focus) = self.workspace().get_focus() else {
+            return;
+        };
+        if self.workspace_mut().layout.handle(message, focus) {
+            self.arrange(self.current_workspace());
+        }
+    }
+
+    pub fn next_tab(&mut self) {
+        // Bring the next window along to the front, keeping the order of windows the same
+        if let Some(window) = self.workspace().neighbour(true) {
+            self.activate(window);
+        }
+    }
+
+    pub fn prev_tab(&mut self) {
+        // Bring the previous window to the front, keeping the order of windows the same
+        if let Some(window) = self.workspace().neighbour(false) {
+            self.activate(window);
+        }
+    }
+
+    pub fn layout_name(&self) -> &'static str {
+        // Get the name of the layout the focused workspace is using
+        self.workspace().layout.name()
+    }
+
+    fn publish_layout(&mut self) {
+        // Let bars know the layout of the focused workspace, through a property on the root,
+        // if it isn't what they already know
+        let (root, name) = (self.root(), self.layout_name());
+        if self.published.insert(root, name) == Some(name) {
+            return;
+        }
+        let property = self.atom("_STARWM_LAYOUT");
+        xcb::change_property(
+            &self.conn,
+            xcb::PROP_MODE_REPLACE as u8,
+            root,
+            property,
+            self.atom("UTF8_STRING"),
+            8,
+            name.as_bytes(),
+        );
+    }
+
+    pub fn is_monocle(&mut self, window: u32) -> bool {
+        // Returns true if the window provided is in monocle mode
+        self.workspace().get_monocle() == Some(window)
+    }
+
+    fn move_window(&self, window: u32, x: i64, y: i64) {
+        // Move a window to a specific X and Y coordinate
+        xcb::configure_window(
+            &self.conn,
+            window,
+            &[
+                (xcb::CONFIG_WINDOW_X as u16, x as u32),
+                (xcb::CONFIG_WINDOW_Y as u16, y as u32),
+            ],
+        );
+    }
+
+    fn reshape_window(&self, window: u32, x: i64, y: i64, w: i64, h: i64) {
+        // Reshape a window to a specific position and size all in one
+        xcb::configure_window(
+            &self.conn,
+            window,
+            &[
+                (xcb::CONFIG_WINDOW_X as u16, x as u32),
+                (xcb::CONFIG_WINDOW_Y as u16, y as u32),
+                (xcb::CONFIG_WINDOW_WIDTH as u16, w as u32),
+                (xcb::CONFIG_WINDOW_HEIGHT as u16, h as u32),
+            ],
+        );
+    }
+
+    fn get_geometry(&self, window: u32) -> Option<Rect> {
+        // Get the position and size of a window
+        let geo = xcb::get_geometry(&self.conn, window).get_reply().ok()?;
+        Some(Rect::new(
+            i64::from(geo.x()),
+            i64::from(geo.y()),
+            u32::from(geo.width()),
+            u32::from(geo.height()),
+        ))
+    }
+
+    fn set_border_width(&self, window: u32, width: u32) {
+        // Set the border width of a window
+        xcb::configure_window(
+            &self.conn,
+            window,
+            &[(xcb::CONFIG_WINDOW_BORDER_WIDTH as u16, width)],
+        );
+    }
+
+    fn set_cursor(conn: &xcb::Connection, screen: &xcb::Screen, k: u16) {
+        // Set the cursor on the screen
+        let c = StarMan::create_cursor(conn, k);
+        xcb::change_window_attributes(conn, screen.root(), &[(xcb::CW_CURSOR, c)]);
+        // The root window keeps hold of the cursor, so it can be let go
+        xcb::free_cursor(conn, c);
+    }
+
+    fn create_cursor(conn: &xcb::Connection, k: u16) -> u32 {
+        // Create a cursor from a glyph in the standard cursor font
+        let f = conn.generate_id();
+        xcb::open_font(conn, f, "cursor");
+        let c = conn.generate_id();
+        xcb::create_glyph_cursor(conn, c, f, f, k, k + 1, 0, 0, 0, 0xffff, 0xffff, 0xffff);
+        xcb::close_font(conn, f);
+        c
+    }
+
+    fn atom(&self, name: &str) -> u32 {
+        // Get the atom for a name, creating it if need be, and only asking X the first time
+        if let Some(&atom) = self.atoms.borrow().get(name) {
+            return atom;
+        }
+        let atom = xcb::intern_atom(&self.conn, false, name)
+            .get_reply()
+            .map_or(xcb::NONE, |reply| reply.atom());
+        if atom != xcb::NONE {
+            self.atoms.borrow_mut().insert(name.to_string(), atom);
+        }
+        atom
+    }
+
+    fn get_property32(&self, window: u32, property: u32, kind: u32) -> Vec<u32> {
+        // Get a property made up of 32 bit values from a window
+        xproto::get_property(&self.conn, false, window, property, kind, 0, 1024)
+            .get_reply()
+            .ok()
+            .filter(|prop| prop.format() == 32)
+            .map_or_else(Vec::new, |prop| prop.value::<u32>().to_vec())
+    }
+
+    fn window_title(&self, window: u32) -> String {
+        // Get the title of a window, preferring the UTF-8 _NET_WM_NAME over WM_NAME
+        let title =
+            self.get_string_property(window, self.atom("_NET_WM_NAME"), self.atom("UTF8_STRING"));
+        if title.is_empty() {
+            self.get_string_property(window, xcb::ATOM_WM_NAME, xcb::ATOM_STRING)
+        } else {
+            title
+        }
+    }
+
+    fn get_string_property(&self, window: u32, property: u32, kind: u32) -> String {
+        // Get a text property from a window
+        xproto::get_property(&self.conn, false, window, property, kind, 0, 1024)
+            .get_reply()
+            .ok()
+            .filter(|prop| prop.format() == 8)
+            .map(|prop| String::from_utf8_lossy(prop.value::<u8>()).into_owned())
+            .unwrap_or_default()
+    }
+
+    fn set_property32(&self, window: u32, property: u32, kind: u32, data: &[u32]) {
+        // Replace a property made up of 32 bit values on a window
+        xcb::change_property(
+            &self.conn,
+            xcb::PROP_MODE_REPLACE as u8,
+            window,
+            property,
+            kind,
+            32,
+            data,
+        );
+    }
+
+    #[rustfmt::skip]
+    fn get_atom_property(&self, window: u32, property: &str) -> u32 {
+        // Get a property from an atom
+        let a = xcb::intern_atom(&self.conn, true, property)
+            .get_reply()
+            .unwrap()
+            .atom();
+        let prop = xproto::get_property(&self.conn, false, window, a, xproto::ATOM_ATOM, 0, 1024)
+            .get_reply()
+            .unwrap();
+        if prop.value_len() == 0 { 42 } else { prop.value()[0] }
+    }
+
+    fn send_protocol(&self, window: u32, protocol: &str, data: [u32; 3]) {
+        // Send a WM_PROTOCOLS client message to a window
+        let protocols = self.atom("WM_PROTOCOLS");
+        let data = xcb::ClientMessageData::from_data32([
+            self.atom(protocol),
+            xcb::CURRENT_TIME,
+            data[0],
+            data[1],
+            data[2],
+        ]);
+        let event = xcb::ClientMessageEvent::new(32, window, protocols, data);
+        xcb::send_event(&self.conn, false, window, xcb::EVENT_MASK_NO_EVENT, &event);
+    }
+
+    fn grab_button(conn: &xcb::Connection, screen: &xcb::Screen, button: u8, mods: u16) {
+        // Tell X to grab all mouse events with specific modifiers and buttons
+        xcb::grab_button(
+            conn,
+            false,
+            screen.root(),
+            DRAG_EVENTS as u16,
+            xcb::GRAB_MODE_ASYNC as u8,
+            xcb::GRAB_MODE_ASYNC as u8,
+            xcb::NONE,
+            xcb::NONE,
+            button,
+            mods,
+        );
+    }
+
+    fn grab_clicks(&self, window: u32) {
+        // Catch clicks on a window when they focus or raise it, holding the pointer until the
+        // click has been passed on
+        if self.conf.focus_model != FocusModel::Click && self.conf.raise != Raise::OnClick {
+            return;
+        }
+        xcb::grab_button(
+            &self.conn,
+            false,
+            window,
+            xcb::EVENT_MASK_BUTTON_PRESS as u16,
+            xcb::GRAB_MODE_SYNC as u8,
+            xcb::GRAB_MODE_ASYNC as u8,
+            xcb::NONE,
+            xcb::NONE,
+            xcb::BUTTON_INDEX_ANY as u8,
+            xcb::MOD_MASK_ANY as u16,
+        );
+    }
+
+    fn regrab_clicks(&self) {
+        // Catch clicks on every window again, after the focus or raise policy has changed
+        for &window in self.clients.keys() {
+            xcb::ungrab_button(
+                &self.conn,
+                xcb::BUTTON_INDEX_ANY as u8,
+                window,
+                xcb::MOD_MASK_ANY as u16,
+            );
+            self.grab_clicks(window);
+        }
     }
 
     fn grab_key(conn: &xcb::Connection, screen: &xcb::Screen, key: &Key, keymap: &SymTable) {
@@ -492,16 +3272,18 @@ impl StarMan {
         StarMan::grab(
             conn,
             screen.root(),
-            xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY as u32,
+            xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY | xcb::EVENT_MASK_PROPERTY_CHANGE,
         );
     }
 
-    fn grab_enter_leave(&self, window: u32) {
-        // Tell X to grab all enter and level events on screen
+    fn grab_client_events(&self, window: u32) {
+        // Tell X to grab all enter, leave and property change events on a window
         StarMan::grab(
             &self.conn,
             window,
-            xcb::EVENT_MASK_ENTER_WINDOW | xcb::EVENT_MASK_LEAVE_WINDOW,
+            xcb::EVENT_MASK_ENTER_WINDOW
+                | xcb::EVENT_MASK_LEAVE_WINDOW
+                | xcb::EVENT_MASK_PROPERTY_CHANGE,
         );
     }
 
@@ -510,6 +3292,42 @@ impl StarMan {
         xcb::set_input_focus(&self.conn, xcb::INPUT_FOCUS_PARENT as u8, window, 0);
     }
 
+    fn get_input_focus(&self) -> u32 {
+        // Ask X which window currently has the input focus
+        xcb::get_input_focus(&self.conn)
+            .get_reply()
+            .map_or(xcb::NONE, |reply| reply.focus())
+    }
+
+    fn activate(&mut self, window: u32) {
+        // Raise and focus a window on the current workspace
+        let previous = self.get_input_focus();
+        if previous != window && self.clients.contains_key(&previous) {
+            self.border_unfocused(previous);
+        }
+        self.raise_window(window);
+        // Windows passed over while cycling don't count as used, until the cycle settles
+        let cycling = self.cycle.is_some();
+        if !self.is_monocle(window) {
+            if cycling {
+                self.workspace_mut().peek_focus(window);
+            } else {
+                self.workspace_mut().set_focus(window);
+            }
+            // Some layouts move things around to keep the focused window in view
+            if self.workspace_mut().layout.focus(window) {
+                self.arrange(self.current_workspace());
+            }
+        }
+        if !cycling {
+            self.remember_focus(window);
+        }
+        self.focus_window(window);
+        self.clear_urgency(window);
+        self.border_focused(window);
+        self.draw_strip(self.current_workspace());
+    }
+
     fn grab(conn: &xcb::Connection, window: u32, events: u32) {
         // Generic helper function to set up an event grab on a window
         xcb::change_window_attributes(conn, window, &[(xcb::CW_EVENT_MASK, events)]);
@@ -517,11 +3335,12 @@ impl StarMan {
 
     pub fn workspace(&self) -> &Workspace {
         // Get the current workspace (immutable operations)
-        &self.workspaces[self.workspace]
+        &self.workspaces[self.current_workspace()]
     }
 
     pub fn workspace_mut(&mut self) -> &mut Workspace {
         // Get the current workspace (mutable operations)
-        &mut self.workspaces[self.workspace]
+        let idx = self.current_workspace();
+        &mut self.workspaces[idx]
     }
 }